@@ -0,0 +1,78 @@
+use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, PrettyFormatter};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io;
+
+fn document(records: usize) -> Value {
+    let records: Vec<Value> = (0..records)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("record {}", i),
+                "enabled": i % 2 == 0,
+                "score": i as f64 * 1.5,
+                "tags": ["alpha", "beta", "gamma"],
+                "parent": null,
+                "nested": { "a": 1, "b": "two", "c": [1.0, 2.0, 3.0] }
+            })
+        })
+        .collect();
+    Value::Array(records)
+}
+
+fn plain<F>(value: &Value, formatter: F) -> io::Result<()>
+where
+    F: serde_json::ser::Formatter,
+{
+    let mut serializer = serde_json::Serializer::with_formatter(io::sink(), formatter);
+    value.serialize(&mut serializer).map_err(io::Error::from)
+}
+
+fn format(c: &mut Criterion) {
+    let mut group = c.benchmark_group("format");
+
+    for records in [10, 1_000] {
+        let value = document(records);
+        let size = serde_json::to_vec_pretty(&value).unwrap().len();
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("pretty", records), &value, |b, value| {
+            b.iter(|| plain(black_box(value), PrettyFormatter::new()))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("colored_pretty", records),
+            &value,
+            |b, value| {
+                b.iter(|| {
+                    ColoredFormatter::new(PrettyFormatter::new()).write_colored_json(
+                        black_box(value),
+                        &mut io::sink(),
+                        ColorMode::On,
+                    )
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("compact", records), &value, |b, value| {
+            b.iter(|| plain(black_box(value), CompactFormatter))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("colored_compact", records),
+            &value,
+            |b, value| {
+                b.iter(|| {
+                    ColoredFormatter::new(CompactFormatter).write_colored_json(
+                        black_box(value),
+                        &mut io::sink(),
+                        ColorMode::On,
+                    )
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, format);
+criterion_main!(benches);
@@ -1,3 +1,5 @@
+#![allow(clippy::single_component_path_imports)]
+
 use colored_json;
 
 use colored_json::prelude::*;
@@ -0,0 +1,168 @@
+//! Pretty print JSON documents with colors
+
+use clap::{Parser, ValueEnum};
+use colored_json::{ColorMode, ColoredFormatter, Output, Styler, SyntaxError, Theme};
+use serde::de::IgnoredAny;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// When to use colors
+    #[arg(long, value_enum, default_value_t = When::Auto)]
+    color: When,
+
+    /// Print each document on a single line
+    #[arg(short, long, conflicts_with = "indent")]
+    compact: bool,
+
+    /// The number of spaces to indent with
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(0..=16))]
+    indent: u8,
+
+    /// The theme to use
+    #[arg(long, default_value = "jq", value_parser = clap::builder::PossibleValuesParser::new(Theme::names()))]
+    theme: String,
+
+    /// Sort the keys of objects, instead of keeping their order
+    #[arg(short = 'S', long)]
+    sort_keys: bool,
+
+    /// The files to read, reads from stdin if none are given
+    files: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum When {
+    Auto,
+    Always,
+    Never,
+}
+
+impl When {
+    fn mode(self, output: Output) -> ColorMode {
+        match self {
+            When::Auto => ColorMode::Auto(output),
+            When::Always => ColorMode::On,
+            When::Never => ColorMode::Off,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        // the reader went away, e.g. when piping into `head`
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Error::Parse(name, err)) => {
+            let report = err.report(cli.color.mode(Output::StdErr)).with_name(&name);
+            eprint!("{report}");
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("colored_json: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(io::Error),
+    Open(PathBuf, io::Error),
+    Parse(String, SyntaxError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{err}"),
+            Error::Open(path, err) => write!(f, "{}: {err}", path.display()),
+            Error::Parse(name, err) => write!(f, "{name}: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+fn run(cli: &Cli) -> Result<(), Error> {
+    let styler = Styler::from_theme(&cli.theme).unwrap_or_default();
+    let indent = vec![b' '; usize::from(cli.indent)];
+
+    if cli.compact {
+        let formatter = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+            .with_sort_keys(cli.sort_keys);
+        print_all(cli, &formatter)
+    } else {
+        let formatter =
+            ColoredFormatter::with_styler(PrettyFormatter::with_indent(&indent), styler)
+                .with_sort_keys(cli.sort_keys);
+        print_all(cli, &formatter)
+    }
+}
+
+fn print_all<F>(cli: &Cli, formatter: &ColoredFormatter<F>) -> Result<(), Error>
+where
+    F: Formatter + Clone,
+{
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if cli.files.is_empty() {
+        print(cli, formatter, "<stdin>", io::stdin().lock(), &mut out)?;
+    }
+
+    for path in &cli.files {
+        let file = File::open(path).map_err(|err| Error::Open(path.clone(), err))?;
+        let name = path.display().to_string();
+        print(cli, formatter, &name, BufReader::new(file), &mut out)?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Print all JSON documents of the input
+fn print<F, R, W>(
+    cli: &Cli,
+    formatter: &ColoredFormatter<F>,
+    name: &str,
+    mut input: R,
+    out: &mut W,
+) -> Result<(), Error>
+where
+    F: Formatter + Clone,
+    R: Read,
+    W: Write,
+{
+    // keep the text, to report where it's invalid
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+
+    // colorize the text of each document, which keeps the order of keys and the numbers
+    let mut documents = serde_json::Deserializer::from_str(&text).into_iter::<IgnoredAny>();
+    let mut start = 0;
+    while let Some(document) = documents.next() {
+        document.map_err(|err| Error::Parse(name.to_string(), SyntaxError::new(&text, err)))?;
+        let end = documents.byte_offset();
+
+        formatter
+            .clone()
+            .write_colored_str(&text[start..end], out, cli.color.mode(Output::StdOut))
+            .map_err(|err| Error::Io(err.into()))?;
+        writeln!(out)?;
+        start = end;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use crate::{Environment, ProcessEnvironment, Styler};
+use yansi::{Color, Style};
+
+/// The colors a terminal is capable of displaying
+///
+/// Styles can use any [`Color`], including [`Color::Fixed`] (256 colors) and [`Color::RGB`]
+/// (truecolor). When writing, colors are downgraded to the nearest color the terminal is capable
+/// of displaying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorDepth {
+    /// The basic ANSI colors
+    Ansi16,
+    /// The 256 color palette
+    Ansi256,
+    /// 24 bit RGB colors
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detect the color depth of the terminal from the process environment
+    pub fn detect() -> Self {
+        Self::detect_with(&ProcessEnvironment)
+    }
+
+    /// Detect the color depth of the terminal, using the provided environment
+    ///
+    /// * `COLORTERM` set to `truecolor` or `24bit` indicates [`ColorDepth::TrueColor`]
+    /// * `TERM` ending with `-direct` indicates [`ColorDepth::TrueColor`]
+    /// * `TERM` containing `256color` indicates [`ColorDepth::Ansi256`]
+    /// * otherwise it is [`ColorDepth::Ansi16`], or [`ColorDepth::TrueColor`] on Windows
+    ///   without `TERM`
+    pub fn detect_with<E>(env: &E) -> Self
+    where
+        E: Environment + ?Sized,
+    {
+        if let Some("truecolor" | "24bit") = env.var("COLORTERM").as_deref() {
+            return ColorDepth::TrueColor;
+        }
+
+        match env.var("TERM") {
+            Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            None if cfg!(windows) => ColorDepth::TrueColor,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// Convert the color to the nearest color which can be displayed with this depth
+    ///
+    /// ```rust
+    /// # use colored_json::{Color, ColorDepth};
+    /// assert_eq!(ColorDepth::Ansi256.downgrade(Color::RGB(255, 0, 0)), Color::Fixed(196));
+    /// assert_eq!(ColorDepth::Ansi16.downgrade(Color::RGB(250, 10, 10)), Color::Red);
+    /// assert_eq!(ColorDepth::TrueColor.downgrade(Color::RGB(1, 2, 3)), Color::RGB(1, 2, 3));
+    /// ```
+    pub fn downgrade(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::Ansi256, Color::RGB(r, g, b)) => Color::Fixed(rgb_to_256(r, g, b)),
+            (ColorDepth::Ansi16, Color::RGB(r, g, b)) => nearest_basic((r, g, b)),
+            // the system colors, including the bright ones, can be displayed as they are
+            (ColorDepth::Ansi16, Color::Fixed(n)) if n >= 16 => nearest_basic(fixed_to_rgb(n)),
+            (_, color) => color,
+        }
+    }
+
+    /// Convert the foreground and background color of the style
+    pub fn downgrade_style(self, style: Style) -> Style {
+        style
+            .fg(self.downgrade(style.fg_color()))
+            .bg(self.downgrade(style.bg_color()))
+    }
+}
+
+impl Styler {
+    /// Convert all styles to colors which can be displayed with the color depth
+    pub fn downgrade(self, depth: ColorDepth) -> Styler {
+        let d = |style| depth.downgrade_style(style);
+        Styler {
+            object_brackets: d(self.object_brackets),
+            object_colon: d(self.object_colon),
+            array_brackets: d(self.array_brackets),
+            key: d(self.key),
+            string_value: d(self.string_value),
+            integer_value: d(self.integer_value),
+            float_value: d(self.float_value),
+            bool_value: d(self.bool_value),
+            true_value: self.true_value.map(d),
+            false_value: self.false_value.map(d),
+            nil_value: d(self.nil_value),
+            escape: self.escape.map(d),
+            error: self.error.map(d),
+            elided: self.elided.map(d),
+            added: d(self.added),
+            removed: d(self.removed),
+            changed: d(self.changed),
+            string_include_quotation: self.string_include_quotation,
+        }
+    }
+}
+
+/// The RGB value of a color, `None` for the default color of the output
+pub(crate) fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Fixed(n) => Some(fixed_to_rgb(n)),
+        Color::RGB(r, g, b) => Some((r, g, b)),
+        color => BASIC_COLORS
+            .iter()
+            .position(|basic| *basic == color)
+            .map(|i| SYSTEM_COLORS[i]),
+    }
+}
+
+/// The intensity levels of the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The first 16 colors of the 256 color palette, using the xterm defaults
+const SYSTEM_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const BASIC_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs().pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn fixed_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => SYSTEM_COLORS[n as usize],
+        16..=231 => {
+            let n = n - 16;
+            (
+                CUBE_LEVELS[(n / 36) as usize],
+                CUBE_LEVELS[(n / 6 % 6) as usize],
+                CUBE_LEVELS[(n % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (n - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = ((u16::from(r) + u16::from(g) + u16::from(b)) / 3) as u8;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23);
+
+    if distance((r, g, b), fixed_to_rgb(gray)) < distance((r, g, b), fixed_to_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    // the bright colors can only be expressed through the 256 color palette, so they get folded
+    // onto their regular counterpart, except for gray, which would turn into invisible black
+    let index = (0..SYSTEM_COLORS.len())
+        .min_by_key(|&i| distance(rgb, SYSTEM_COLORS[i]))
+        .unwrap_or_default();
+    match index {
+        8 => Color::Fixed(8),
+        _ => BASIC_COLORS[index % 8],
+    }
+}
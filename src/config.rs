@@ -0,0 +1,426 @@
+use crate::Styler;
+use std::fmt;
+use yansi::{Color, Style};
+
+/// The name of the environment variable `jq` reads its colors from
+pub const JQ_COLORS: &str = "JQ_COLORS";
+
+/// The fields of `JQ_COLORS`, in order
+const JQ_FIELDS: [&str; 8] = [
+    "null", "false", "true", "numbers", "strings", "arrays", "objects", "keys",
+];
+
+/// Error parsing a style definition
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StyleError {
+    /// A style definition could not be parsed
+    InvalidStyle {
+        /// the value which failed to parse
+        value: String,
+        /// the reason why the value is invalid
+        reason: String,
+    },
+    /// An entry of a style definition could not be parsed
+    InvalidEntry {
+        /// the name of the entry
+        name: String,
+        /// the value which failed to parse
+        value: String,
+        /// the reason why the value is invalid
+        reason: String,
+    },
+    /// `JQ_COLORS` had more fields than supported
+    TooManyFields(usize),
+    /// The requested theme does not exist
+    UnknownTheme(String),
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StyleError::InvalidStyle { value, reason } => {
+                write!(f, "invalid style '{}': {}", value, reason)
+            }
+            StyleError::InvalidEntry {
+                name,
+                value,
+                reason,
+            } => write!(f, "invalid style for '{}' ('{}'): {}", name, value, reason),
+            StyleError::TooManyFields(count) => write!(
+                f,
+                "too many fields in {}: found {}, expected at most {} ({})",
+                JQ_COLORS,
+                count,
+                JQ_FIELDS.len(),
+                JQ_FIELDS.join(":")
+            ),
+            StyleError::UnknownTheme(name) => write!(
+                f,
+                "unknown theme '{}', possible values: {}",
+                name,
+                crate::Theme::names().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StyleError {}
+
+impl Styler {
+    /// Create a styler from colors in the format of `jq`'s `JQ_COLORS` variable
+    ///
+    /// The value consists of colon separated SGR codes, for: null, false, true, numbers, strings,
+    /// arrays, objects and object keys. Missing fields keep the style of [`Styler::default`].
+    ///
+    /// ```rust
+    /// # use colored_json::{Color, Style, Styler};
+    /// let styler = Styler::from_jq_colors("1;30:0;39:0;39:0;39:0;32:1;39:1;39:34;1").unwrap();
+    /// assert_eq!(styler.key, Style::new(Color::Blue).bold());
+    ///
+    /// assert!(Styler::from_jq_colors("0;3x").is_err());
+    /// ```
+    pub fn from_jq_colors(colors: &str) -> Result<Styler, StyleError> {
+        Styler::default().with_jq_colors(colors)
+    }
+
+    /// Create a styler from the `JQ_COLORS` environment variable, if it is set
+    pub fn from_jq_colors_env() -> Result<Option<Styler>, StyleError> {
+        std::env::var(JQ_COLORS)
+            .ok()
+            .map(|colors| Styler::from_jq_colors(&colors))
+            .transpose()
+    }
+
+    /// Apply colors in the format of `jq`'s `JQ_COLORS` variable on top of this styler
+    ///
+    /// See [`Styler::from_jq_colors`] for the format.
+    pub fn with_jq_colors(mut self, colors: &str) -> Result<Styler, StyleError> {
+        let fields: Vec<&str> = colors.split(':').collect();
+        if fields.len() > JQ_FIELDS.len() {
+            return Err(StyleError::TooManyFields(fields.len()));
+        }
+
+        for (name, value) in JQ_FIELDS.iter().zip(fields) {
+            let style = parse_sgr(value).map_err(|reason| StyleError::InvalidEntry {
+                name: name.to_string(),
+                value: value.to_string(),
+                reason,
+            })?;
+
+            match *name {
+                "null" => self.nil_value = style,
+                "false" => self.false_value = Some(style),
+                "true" => self.true_value = Some(style),
+                "numbers" => {
+                    self.integer_value = style;
+                    self.float_value = style;
+                }
+                "strings" => self.string_value = style,
+                "arrays" => self.array_brackets = style,
+                "objects" => {
+                    self.object_brackets = style;
+                    self.object_colon = style;
+                }
+                "keys" => self.key = style,
+                _ => unreachable!("unknown jq field: {}", name),
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+const NAMED_COLORS: [(&str, Color); 8] = [
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+];
+
+/// Parse a style from a human readable definition
+///
+/// The definition is a whitespace separated list of attributes (`bold`, `dimmed`, `italic`,
+/// `underline`, `blink`, `invert`, `hidden`, `strikethrough`), and colors. The first color is the
+/// foreground color, a color following `on` is the background color.
+///
+/// Colors are either a name (`black`, `red`, `green`, `yellow`, `blue`, `magenta`, `cyan`,
+/// `white`, `default`, optionally prefixed with `bright-`), a number of the 256 color palette, or
+/// an RGB value in the form of `#rrggbb`.
+///
+/// ```rust
+/// # use colored_json::{parse_style, Color, Style};
+/// assert_eq!(parse_style("blue bold").unwrap(), Style::new(Color::Blue).bold());
+/// assert_eq!(
+///     parse_style("#ff8000 on 236").unwrap(),
+///     Style::new(Color::RGB(255, 128, 0)).bg(Color::Fixed(236))
+/// );
+/// assert!(parse_style("blue green").is_err());
+/// ```
+pub fn parse_style(definition: &str) -> Result<Style, StyleError> {
+    style_from_definition(definition).map_err(|reason| StyleError::InvalidStyle {
+        value: definition.to_string(),
+        reason,
+    })
+}
+
+fn style_from_definition(definition: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    let mut foreground = false;
+    let mut words = definition.split_whitespace();
+
+    while let Some(word) = words.next() {
+        let lower = word.to_ascii_lowercase();
+        style = match lower.as_str() {
+            "bold" => style.bold(),
+            "dim" | "dimmed" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            "blink" => style.blink(),
+            "invert" | "reverse" => style.invert(),
+            "hidden" => style.hidden(),
+            "strikethrough" => style.strikethrough(),
+            "on" => {
+                let color = words
+                    .next()
+                    .ok_or_else(|| "missing background color after 'on'".to_string())?;
+                if style.bg_color() != Color::Unset {
+                    return Err("more than one background color".into());
+                }
+                style.bg(parse_color(color)?)
+            }
+            _ => {
+                if foreground {
+                    return Err(format!(
+                        "unknown attribute or more than one foreground color: '{}'",
+                        word
+                    ));
+                }
+                foreground = true;
+                style.fg(parse_color(word)?)
+            }
+        };
+    }
+
+    Ok(style)
+}
+
+fn parse_color(word: &str) -> Result<Color, String> {
+    let lower = word.to_ascii_lowercase();
+
+    if lower == "default" {
+        return Ok(Color::Default);
+    }
+
+    if let Some(hex) = lower.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::RGB(r, g, b)),
+            _ => Err(format!("invalid RGB color: '{}'", word)),
+        };
+    }
+
+    if let Ok(n) = lower.parse::<u8>() {
+        return Ok(Color::Fixed(n));
+    }
+
+    let (name, bright) = match lower
+        .strip_prefix("bright-")
+        .or_else(|| lower.strip_prefix("bright"))
+    {
+        Some(name) => (name, true),
+        None => (lower.as_str(), false),
+    };
+
+    NAMED_COLORS
+        .iter()
+        .position(|(n, _)| *n == name)
+        .map(|i| {
+            if bright {
+                Color::Fixed(8 + i as u8)
+            } else {
+                NAMED_COLORS[i].1
+            }
+        })
+        .ok_or_else(|| format!("unknown color or attribute: '{}'", word))
+}
+
+/// Parse a style from a list of SGR codes, separated by `;`
+fn parse_sgr(codes: &str) -> Result<Style, String> {
+    let mut style = Style::default();
+    if codes.is_empty() {
+        return Ok(style);
+    }
+
+    let codes = codes
+        .split(';')
+        .map(|code| {
+            code.parse::<u8>()
+                .map_err(|_| format!("invalid SGR code '{}'", code))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut codes = codes.into_iter();
+
+    let extended = |codes: &mut std::vec::IntoIter<u8>| match codes.next() {
+        Some(5) => codes
+            .next()
+            .map(Color::Fixed)
+            .ok_or_else(|| "missing palette index".to_string()),
+        Some(2) => match (codes.next(), codes.next(), codes.next()) {
+            (Some(r), Some(g), Some(b)) => Ok(Color::RGB(r, g, b)),
+            _ => Err("incomplete RGB color".to_string()),
+        },
+        _ => Err("invalid extended color".to_string()),
+    };
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.bold(),
+            2 => style.dimmed(),
+            3 => style.italic(),
+            4 => style.underline(),
+            5 | 6 => style.blink(),
+            7 => style.invert(),
+            8 => style.hidden(),
+            9 => style.strikethrough(),
+            30..=37 => style.fg(NAMED_COLORS[usize::from(code - 30)].1),
+            38 => style.fg(extended(&mut codes)?),
+            39 => style.fg(Color::Default),
+            40..=47 => style.bg(NAMED_COLORS[usize::from(code - 40)].1),
+            48 => style.bg(extended(&mut codes)?),
+            49 => style.bg(Color::Default),
+            90..=97 => style.fg(Color::Fixed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Fixed(code - 100 + 8)),
+            _ => return Err(format!("unsupported SGR code '{}'", code)),
+        };
+    }
+
+    Ok(style)
+}
+
+#[cfg(feature = "config")]
+mod de {
+    use super::{style_from_definition, StyleError};
+    use crate::{Style, Styler};
+    use serde::{de, Deserialize, Deserializer};
+
+    /// The serialized form of a [`Styler`]
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct StylerConfig {
+        theme: Option<String>,
+        object_brackets: Option<String>,
+        object_colon: Option<String>,
+        array_brackets: Option<String>,
+        key: Option<String>,
+        string_value: Option<String>,
+        integer_value: Option<String>,
+        float_value: Option<String>,
+        bool_value: Option<String>,
+        true_value: Option<String>,
+        false_value: Option<String>,
+        nil_value: Option<String>,
+        escape: Option<String>,
+        error: Option<String>,
+        elided: Option<String>,
+        added: Option<String>,
+        removed: Option<String>,
+        changed: Option<String>,
+        string_include_quotation: Option<bool>,
+    }
+
+    fn apply(target: &mut Style, name: &str, value: Option<String>) -> Result<(), StyleError> {
+        if let Some(value) = value {
+            *target = style_from_definition(&value).map_err(|reason| StyleError::InvalidEntry {
+                name: name.to_string(),
+                value,
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn apply_optional(
+        target: &mut Option<Style>,
+        name: &str,
+        value: Option<String>,
+    ) -> Result<(), StyleError> {
+        if value.is_some() {
+            apply(target.get_or_insert_with(Style::default), name, value)?;
+        }
+        Ok(())
+    }
+
+    impl StylerConfig {
+        fn into_styler(self) -> Result<Styler, StyleError> {
+            let mut styler = match self.theme {
+                Some(theme) => Styler::from_theme(&theme).ok_or(StyleError::UnknownTheme(theme))?,
+                None => Styler::default(),
+            };
+
+            apply(
+                &mut styler.object_brackets,
+                "object_brackets",
+                self.object_brackets,
+            )?;
+            apply(&mut styler.object_colon, "object_colon", self.object_colon)?;
+            apply(
+                &mut styler.array_brackets,
+                "array_brackets",
+                self.array_brackets,
+            )?;
+            apply(&mut styler.key, "key", self.key)?;
+            apply(&mut styler.string_value, "string_value", self.string_value)?;
+            apply(
+                &mut styler.integer_value,
+                "integer_value",
+                self.integer_value,
+            )?;
+            apply(&mut styler.float_value, "float_value", self.float_value)?;
+            apply(&mut styler.bool_value, "bool_value", self.bool_value)?;
+            apply_optional(&mut styler.true_value, "true_value", self.true_value)?;
+            apply_optional(&mut styler.false_value, "false_value", self.false_value)?;
+            apply(&mut styler.nil_value, "nil_value", self.nil_value)?;
+            apply_optional(&mut styler.escape, "escape", self.escape)?;
+            apply_optional(&mut styler.error, "error", self.error)?;
+            apply_optional(&mut styler.elided, "elided", self.elided)?;
+            apply(&mut styler.added, "added", self.added)?;
+            apply(&mut styler.removed, "removed", self.removed)?;
+            apply(&mut styler.changed, "changed", self.changed)?;
+            if let Some(include) = self.string_include_quotation {
+                styler.string_include_quotation = include;
+            }
+
+            Ok(styler)
+        }
+    }
+
+    /// Deserialize a styler from a configuration, e.g. TOML or JSON
+    ///
+    /// All entries are optional. Missing entries are taken from the theme named by `theme`, or
+    /// from [`Styler::default`]. Styles use the format of [`parse_style`](crate::parse_style).
+    ///
+    /// ```toml
+    /// theme = "solarized-dark"
+    /// key = "blue bold"
+    /// string_value = "#2aa198"
+    /// nil_value = "red on white"
+    /// ```
+    impl<'de> Deserialize<'de> for Styler {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            StylerConfig::deserialize(deserializer)?
+                .into_styler()
+                .map_err(de::Error::custom)
+        }
+    }
+}
@@ -0,0 +1,639 @@
+//! Colored diffs between two JSON documents
+//!
+//! The documents are compared structurally: objects by their keys, arrays by the index of their
+//! values. The differences are shown in the layout of pretty printed JSON, or as a list of the
+//! changed paths. In tests, [`assert_json_eq!`](crate::assert_json_eq) shows the diff of values
+//! which are not equal.
+//!
+//! ```rust
+//! # use colored_json::diff::{Diff, Layout};
+//! # use colored_json::ColorMode;
+//! # use serde_json::json;
+//! let expected = json!({"id": 1, "tags": ["a", "b"]});
+//! let actual = json!({"id": 2, "tags": ["a"]});
+//!
+//! let s = colored_json::diff::to_colored_diff(&expected, &actual, ColorMode::Off)?;
+//! assert_eq!(
+//!     s,
+//!     r#"  {
+//! -   "id": 1,
+//! +   "id": 2,
+//!     "tags": [
+//!       "a"
+//! -     "b"
+//!     ]
+//!   }"#
+//! );
+//!
+//! let s = Diff::new()
+//!     .with_layout(Layout::ChangedOnly)
+//!     .to_colored_diff(&expected, &actual, ColorMode::Off)?;
+//! assert_eq!(s, "~ /id: 1 → 2\n- /tags/1: \"b\"");
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use crate::highlight::Role;
+use crate::{colored, AnsiHighlighter, ColorMode, ColoredFormatter, CompactFormatter, Styler};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::io::{self, Write};
+use yansi::Style;
+
+/// How the differences are shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    /// both documents merged, with removed lines marked by `-` and added lines by `+`
+    #[default]
+    Unified,
+    /// the old document on the left and the new one on the right, with changed lines marked by
+    /// `|`, removed lines by `<` and added lines by `>`
+    SideBySide,
+    /// only the paths of changed values, like `~ /id: 1 → 2`, with `-` for removed and `+` for
+    /// added values
+    ChangedOnly,
+}
+
+/// Writes colored diffs between two JSON documents
+///
+/// Added, removed and changed values get the [`Styler::added`], [`Styler::removed`] and
+/// [`Styler::changed`] styles, unchanged values the styles of their tokens.
+#[derive(Clone, Debug, Default)]
+pub struct Diff {
+    styler: Styler,
+    layout: Layout,
+}
+
+impl Diff {
+    /// Create a diff with the default styler and the [`Layout::Unified`] layout
+    pub fn new() -> Self {
+        Diff::default()
+    }
+
+    /// Set the styler to color the output with
+    pub fn with_styler(mut self, styler: Styler) -> Self {
+        self.styler = styler;
+        self
+    }
+
+    /// Set how the differences are shown, defaults to [`Layout::Unified`]
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Compare two values, as a colored String
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if the `Serialize` implementation of a value decides to fail, or
+    /// if it contains a map with non-string keys.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_colored_diff<A, B>(
+        &self,
+        old: &A,
+        new: &B,
+        mode: ColorMode,
+    ) -> serde_json::Result<String>
+    where
+        A: ?Sized + Serialize,
+        B: ?Sized + Serialize,
+    {
+        let mut writer: Vec<u8> = Vec::with_capacity(128);
+
+        self.write_colored_diff(old, new, &mut writer, mode)?;
+
+        Ok(String::from_utf8_lossy(&writer).to_string())
+    }
+
+    /// Compare two values, writing the colored diff into the IO stream
+    ///
+    /// # Errors
+    ///
+    /// Serialization can fail if the `Serialize` implementation of a value decides to fail, or
+    /// if it contains a map with non-string keys. Writing can fail as well.
+    pub fn write_colored_diff<W, A, B>(
+        &self,
+        old: &A,
+        new: &B,
+        writer: &mut W,
+        mode: ColorMode,
+    ) -> serde_json::Result<()>
+    where
+        W: io::Write,
+        A: ?Sized + Serialize,
+        B: ?Sized + Serialize,
+    {
+        let old = serde_json::to_value(old)?;
+        let new = serde_json::to_value(new)?;
+
+        let mut chunks = Vec::new();
+        compare(
+            &mut chunks,
+            String::new(),
+            None,
+            &old,
+            &new,
+            0,
+            (false, false),
+        );
+
+        let mut renderer = Renderer {
+            formatter: ColoredFormatter::with_styler(CompactFormatter {}, self.styler)
+                .for_mode(mode),
+            first: true,
+        };
+        let result = match self.layout {
+            Layout::Unified => renderer.unified(writer, &chunks),
+            Layout::SideBySide => renderer.side_by_side(writer, &chunks),
+            Layout::ChangedOnly => renderer.changed_only(writer, &chunks),
+        };
+        result.map_err(serde_json::Error::io)
+    }
+}
+
+/// Compare two values, as a unified diff, see [`Diff`]
+///
+/// # Errors
+///
+/// Serialization can fail if the `Serialize` implementation of a value decides to fail, or if it
+/// contains a map with non-string keys.
+pub fn to_colored_diff<A, B>(old: &A, new: &B, mode: ColorMode) -> serde_json::Result<String>
+where
+    A: ?Sized + Serialize,
+    B: ?Sized + Serialize,
+{
+    Diff::new().to_colored_diff(old, new, mode)
+}
+
+/// Compare two values, writing a unified diff into the IO stream, see [`Diff`]
+///
+/// # Errors
+///
+/// Serialization can fail if the `Serialize` implementation of a value decides to fail, or if it
+/// contains a map with non-string keys. Writing can fail as well.
+pub fn write_colored_diff<W, A, B>(
+    old: &A,
+    new: &B,
+    writer: &mut W,
+    mode: ColorMode,
+) -> serde_json::Result<()>
+where
+    W: io::Write,
+    A: ?Sized + Serialize,
+    B: ?Sized + Serialize,
+{
+    Diff::new().write_colored_diff(old, new, writer, mode)
+}
+
+/// Asserts that two values serialize to equal JSON, showing a colored diff if they don't
+///
+/// Both values may be of different types, like a struct and a [`serde_json::Value`], as long as
+/// they implement `Serialize`. On failure, the panic message contains a unified diff of their
+/// JSON, with the left value as the old and the right value as the new document. It is colored
+/// according to [`ColorMode::for_tests`](crate::ColorMode::for_tests), so not when the output
+/// is captured or in CI.
+///
+/// Like [`assert_eq!`], a custom message can be passed after the values.
+///
+/// ```rust
+/// # use colored_json::assert_json_eq;
+/// # use serde_json::json;
+/// assert_json_eq!(json!({"a": [1, 2]}), json!({"a": [1, 2]}));
+/// assert_json_eq!(json!([1]), vec![1], "for the {} time", 1);
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::diff::assert_json_eq(&$left, &$right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::diff::assert_json_eq(
+            &$left,
+            &$right,
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+/// The implementation of [`assert_json_eq!`]
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_json_eq<A, B>(left: &A, right: &B, message: Option<fmt::Arguments<'_>>)
+where
+    A: ?Sized + Serialize,
+    B: ?Sized + Serialize,
+{
+    let to_value = |value: Result<Value, serde_json::Error>, side: &str| {
+        value.unwrap_or_else(|error| panic!("the {side} value can't be serialized: {error}"))
+    };
+    let left = to_value(serde_json::to_value(left), "left");
+    let right = to_value(serde_json::to_value(right), "right");
+    if left == right {
+        return;
+    }
+
+    let diff = Diff::new()
+        .to_colored_diff(&left, &right, ColorMode::for_tests())
+        .unwrap_or_else(|error| error.to_string());
+    match message {
+        Some(message) => {
+            panic!("assertion `left == right` failed: {message}\n(- left, + right)\n{diff}")
+        }
+        None => panic!("assertion `left == right` failed\n(- left, + right)\n{diff}"),
+    }
+}
+
+/// A line of pretty printed JSON
+struct Line {
+    depth: usize,
+    /// the escaped key, with quotes
+    key: Option<String>,
+    /// a scalar value, or a bracket
+    text: String,
+    comma: bool,
+}
+
+impl Line {
+    /// The width of the line, without the marker
+    fn width(&self) -> usize {
+        INDENT.len() * self.depth
+            + self.key.as_ref().map_or(0, |key| key.chars().count() + 2)
+            + self.text.chars().count()
+            + usize::from(self.comma)
+    }
+}
+
+const INDENT: &str = "  ";
+
+/// A part of the diff
+enum Chunk<'a> {
+    /// a line of both documents, which may only differ by its comma
+    Same(Line, Line),
+    /// a value which differs, with its lines in both documents
+    Change {
+        path: String,
+        old: Option<&'a Value>,
+        new: Option<&'a Value>,
+        removed: Vec<Line>,
+        added: Vec<Line>,
+    },
+}
+
+/// Compare two values at the path, `commas` tells if they are followed by a comma
+fn compare<'a>(
+    chunks: &mut Vec<Chunk<'a>>,
+    path: String,
+    key: Option<&str>,
+    old: &'a Value,
+    new: &'a Value,
+    depth: usize,
+    commas: (bool, bool),
+) {
+    let key = key.map(escape_key);
+    let (brackets, entries) = match (old, new) {
+        _ if old == new => {
+            let mut removed = Vec::new();
+            let mut added = Vec::new();
+            lines(&mut removed, key.clone(), old, depth, commas.0);
+            lines(&mut added, key, new, depth, commas.1);
+            chunks.extend(
+                removed
+                    .into_iter()
+                    .zip(added)
+                    .map(|(old, new)| Chunk::Same(old, new)),
+            );
+            return;
+        }
+        (Value::Object(old), Value::Object(new)) if !old.is_empty() && !new.is_empty() => {
+            // the keys of the old object, followed by the added keys
+            let keys = old
+                .keys()
+                .chain(new.keys().filter(|key| !old.contains_key(*key)));
+            let mut entries = Vec::new();
+            let (mut old_index, mut new_index) = (0, 0);
+            for key in keys {
+                let entry = (old.get(key), new.get(key));
+                old_index += usize::from(entry.0.is_some());
+                new_index += usize::from(entry.1.is_some());
+                let commas = (old_index < old.len(), new_index < new.len());
+                let path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                entries.push((path, Some(key.as_str()), entry, commas));
+            }
+            (("{", "}"), entries)
+        }
+        (Value::Array(old), Value::Array(new)) if !old.is_empty() && !new.is_empty() => {
+            let entries = (0..old.len().max(new.len()))
+                .map(|i| {
+                    let commas = (i + 1 < old.len(), i + 1 < new.len());
+                    (
+                        format!("{path}/{i}"),
+                        None,
+                        (old.get(i), new.get(i)),
+                        commas,
+                    )
+                })
+                .collect();
+            (("[", "]"), entries)
+        }
+        _ => {
+            let mut removed = Vec::new();
+            let mut added = Vec::new();
+            lines(&mut removed, key.clone(), old, depth, commas.0);
+            lines(&mut added, key, new, depth, commas.1);
+            chunks.push(Chunk::Change {
+                path,
+                old: Some(old),
+                new: Some(new),
+                removed,
+                added,
+            });
+            return;
+        }
+    };
+
+    let line = |key, text: &str, comma| Line {
+        depth,
+        key,
+        text: text.to_string(),
+        comma,
+    };
+    chunks.push(Chunk::Same(
+        line(key.clone(), brackets.0, false),
+        line(key, brackets.0, false),
+    ));
+    for (path, key, entry, commas) in entries {
+        match entry {
+            (Some(old), Some(new)) => compare(chunks, path, key, old, new, depth + 1, commas),
+            (old, new) => {
+                let mut removed = Vec::new();
+                let mut added = Vec::new();
+                if let Some(old) = old {
+                    lines(&mut removed, key.map(escape_key), old, depth + 1, commas.0);
+                }
+                if let Some(new) = new {
+                    lines(&mut added, key.map(escape_key), new, depth + 1, commas.1);
+                }
+                chunks.push(Chunk::Change {
+                    path,
+                    old,
+                    new,
+                    removed,
+                    added,
+                });
+            }
+        }
+    }
+    chunks.push(Chunk::Same(
+        line(None, brackets.1, commas.0),
+        line(None, brackets.1, commas.1),
+    ));
+}
+
+/// The lines of a pretty printed value
+fn lines(out: &mut Vec<Line>, key: Option<String>, value: &Value, depth: usize, comma: bool) {
+    let mut container = |key, open: &str, close: &str, entries: Vec<(Option<String>, &Value)>| {
+        let len = entries.len();
+        out.push(Line {
+            depth,
+            key,
+            text: open.to_string(),
+            comma: false,
+        });
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            lines(out, key, value, depth + 1, i + 1 < len);
+        }
+        out.push(Line {
+            depth,
+            key: None,
+            text: close.to_string(),
+            comma,
+        });
+    };
+
+    match value {
+        Value::Array(values) if !values.is_empty() => container(
+            key,
+            "[",
+            "]",
+            values.iter().map(|value| (None, value)).collect(),
+        ),
+        Value::Object(map) if !map.is_empty() => container(
+            key,
+            "{",
+            "}",
+            map.iter()
+                .map(|(key, value)| (Some(escape_key(key)), value))
+                .collect(),
+        ),
+        _ => out.push(Line {
+            depth,
+            key,
+            text: value.to_string(),
+            comma,
+        }),
+    }
+}
+
+fn escape_key(key: &str) -> String {
+    Value::from(key).to_string()
+}
+
+/// Writes the chunks of a diff
+struct Renderer {
+    formatter: ColoredFormatter<CompactFormatter, AnsiHighlighter>,
+    /// nothing was written yet
+    first: bool,
+}
+
+impl Renderer {
+    fn unified<W>(&mut self, writer: &mut W, chunks: &[Chunk<'_>]) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        for chunk in chunks {
+            match chunk {
+                Chunk::Same(_, line) => {
+                    self.newline(writer)?;
+                    writer.write_all(b"  ")?;
+                    self.write_line(writer, line)?;
+                }
+                Chunk::Change { removed, added, .. } => {
+                    for line in removed {
+                        self.newline(writer)?;
+                        self.write_marked(writer, Role::Removed, "- ", line)?;
+                    }
+                    for line in added {
+                        self.newline(writer)?;
+                        self.write_marked(writer, Role::Added, "+ ", line)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn side_by_side<W>(&mut self, writer: &mut W, chunks: &[Chunk<'_>]) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let mut rows = Vec::new();
+        for chunk in chunks {
+            match chunk {
+                Chunk::Same(old, new) => rows.push((None, Some(old), Some(new))),
+                Chunk::Change { removed, added, .. } => {
+                    for i in 0..removed.len().max(added.len()) {
+                        let (old, new) = (removed.get(i), added.get(i));
+                        let role = match (old, new) {
+                            (Some(_), Some(_)) => Role::Changed,
+                            (Some(_), None) => Role::Removed,
+                            _ => Role::Added,
+                        };
+                        rows.push((Some(role), old, new));
+                    }
+                }
+            }
+        }
+
+        let width = rows
+            .iter()
+            .filter_map(|(_, old, _)| old.map(Line::width))
+            .max()
+            .unwrap_or(0);
+
+        for (role, old, new) in rows {
+            self.newline(writer)?;
+            let marker = match role {
+                None => "   ",
+                Some(Role::Changed) => " | ",
+                Some(Role::Removed) => " < ",
+                _ => " > ",
+            };
+            match (role, old) {
+                (None, Some(old)) => self.write_line(writer, old)?,
+                (Some(role), Some(old)) => self.write_marked(writer, role, "", old)?,
+                _ => {}
+            }
+            let padding = width - old.map_or(0, Line::width);
+            write!(writer, "{:padding$}", "")?;
+            match (role, new) {
+                (None, Some(new)) => {
+                    writer.write_all(marker.as_bytes())?;
+                    self.write_line(writer, new)?;
+                }
+                (Some(role), new) => {
+                    let style = self.style(role);
+                    colored(&mut self.formatter.painter, writer, role, style, |w| {
+                        w.write_all(marker.as_bytes())
+                    })?;
+                    if let Some(new) = new {
+                        self.write_marked(writer, role, "", new)?;
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn changed_only<W>(&mut self, writer: &mut W, chunks: &[Chunk<'_>]) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        for chunk in chunks {
+            let Chunk::Change { path, old, new, .. } = chunk else {
+                continue;
+            };
+            // the root has an empty path
+            let path = match path.as_str() {
+                "" => String::new(),
+                path => format!("{path}: "),
+            };
+            let (role, text) = match (old, new) {
+                (Some(old), Some(new)) => (Role::Changed, format!("~ {path}{old} → {new}")),
+                (Some(old), None) => (Role::Removed, format!("- {path}{old}")),
+                (None, Some(new)) => (Role::Added, format!("+ {path}{new}")),
+                (None, None) => continue,
+            };
+            self.newline(writer)?;
+            let style = self.style(role);
+            colored(&mut self.formatter.painter, writer, role, style, |w| {
+                w.write_all(text.as_bytes())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Separate the lines
+    fn newline<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        if !std::mem::take(&mut self.first) {
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write an unchanged line, with the styles of its tokens
+    fn write_line<W>(&mut self, writer: &mut W, line: &Line) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let f = &mut self.formatter;
+        writer.write_all(INDENT.repeat(line.depth).as_bytes())?;
+        if let Some(key) = &line.key {
+            f.in_object_key = true;
+            f.write_string_token(writer, key)?;
+            f.in_object_key = false;
+            colored(
+                &mut f.painter,
+                writer,
+                Role::Colon,
+                f.styler.object_colon,
+                |w| w.write_all(b":"),
+            )?;
+            writer.write_all(b" ")?;
+        }
+        f.write_lexed(writer, &line.text, None)?;
+        if line.comma {
+            writer.write_all(b",")?;
+        }
+        Ok(())
+    }
+
+    /// Write a line in the style of the role, after the marker
+    fn write_marked<W>(
+        &mut self,
+        writer: &mut W,
+        role: Role,
+        marker: &str,
+        line: &Line,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let style = self.style(role);
+        colored(&mut self.formatter.painter, writer, role, style, |w| {
+            w.write_all(marker.as_bytes())?;
+            w.write_all(INDENT.repeat(line.depth).as_bytes())?;
+            if let Some(key) = &line.key {
+                write!(w, "{key}: ")?;
+            }
+            w.write_all(line.text.as_bytes())?;
+            if line.comma {
+                w.write_all(b",")?;
+            }
+            Ok(())
+        })
+    }
+
+    fn style(&self, role: Role) -> Style {
+        let styler = &self.formatter.styler;
+        match role {
+            Role::Added => styler.added,
+            Role::Removed => styler.removed,
+            _ => styler.changed,
+        }
+    }
+}
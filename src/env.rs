@@ -0,0 +1,46 @@
+use crate::Output;
+use std::io::{self, IsTerminal};
+
+/// Source of the information `ColorMode::Auto` uses to decide if color should be used.
+///
+/// The default implementation is [`ProcessEnvironment`], which looks at the real process
+/// environment and the standard streams. A custom implementation allows to evaluate the decision
+/// without touching the process environment, e.g. for testing.
+pub trait Environment {
+    /// Get the value of the environment variable `name`, if it is set.
+    fn var(&self, name: &str) -> Option<String>;
+
+    /// Check if the `output` is connected to a terminal.
+    fn is_terminal(&self, output: Output) -> bool;
+
+    /// Get the command line arguments, without the name of the program.
+    ///
+    /// Defaults to no arguments.
+    fn args(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The environment of the current process
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessEnvironment;
+
+impl Environment for ProcessEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        std::env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+    }
+
+    fn is_terminal(&self, output: Output) -> bool {
+        match output {
+            Output::StdOut => io::stdout().is_terminal(),
+            Output::StdErr => io::stderr().is_terminal(),
+        }
+    }
+
+    fn args(&self) -> Vec<String> {
+        std::env::args_os()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+}
@@ -0,0 +1,214 @@
+use crate::ColorMode;
+use std::fmt;
+use yansi::{Color, Paint, Style};
+
+/// The number of lines shown before the line containing the error
+const CONTEXT_LINES: usize = 2;
+
+/// Invalid JSON text, with the location of the error
+///
+/// Besides the location, it keeps the lines of the text around the error, so that it can be
+/// reported like a compiler diagnostic, see [`SyntaxError::report`].
+///
+/// ```rust
+/// # use colored_json::{ColorMode, SyntaxError, ToColoredJson};
+/// let json = "{\n  \"a\": 1\n  \"b\": 2\n}";
+/// let error = json
+///     .to_colored_json(ColorMode::Off)
+///     .map_err(|err| SyntaxError::new(json, err))
+///     .unwrap_err();
+///
+/// assert_eq!(
+///     error.report(ColorMode::Off).to_string(),
+///     "error: expected `,` or `}`
+///  --> line 3, column 3
+///   |
+/// 1 | {
+/// 2 |   \"a\": 1
+/// 3 |   \"b\": 2
+///   |   ^
+/// "
+/// );
+/// ```
+#[derive(Debug)]
+pub struct SyntaxError {
+    offset: usize,
+    error: serde_json::Error,
+    /// the lines before, and the line of the error, with their numbers
+    snippet: Vec<(usize, String)>,
+}
+
+impl SyntaxError {
+    /// Create an error for the text, from an error reported by the parser while parsing it
+    pub fn new(json: &str, error: serde_json::Error) -> Self {
+        let line = error.line();
+
+        let offset = if error.is_eof() {
+            json.len()
+        } else {
+            // the parser reports the column of the last byte it read
+            let line_start: usize = json
+                .split_inclusive('\n')
+                .take(line.saturating_sub(1))
+                .map(str::len)
+                .sum();
+            (line_start + error.column().saturating_sub(1)).min(json.len())
+        };
+
+        let first = line.saturating_sub(CONTEXT_LINES).max(1);
+        let snippet = match line {
+            // not a syntax error, but e.g. an I/O error
+            0 => Vec::new(),
+            _ => (first..=line)
+                .zip(
+                    json.split('\n')
+                        .skip(first - 1)
+                        .chain(std::iter::repeat("")),
+                )
+                .map(|(number, text)| (number, text.trim_end_matches('\r').to_string()))
+                .collect(),
+        };
+
+        SyntaxError {
+            offset,
+            error,
+            snippet,
+        }
+    }
+
+    /// The byte offset in the text the error was found at
+    ///
+    /// For truncated input, this is the length of the text.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The line the error was found in, starting at 1
+    pub fn line(&self) -> usize {
+        self.error.line()
+    }
+
+    /// The column the error was found at, starting at 1
+    pub fn column(&self) -> usize {
+        self.error.column()
+    }
+
+    /// The error reported by the parser
+    pub fn error(&self) -> &serde_json::Error {
+        &self.error
+    }
+
+    /// A report of the error, showing the lines of the text around it
+    ///
+    /// The report ends with a newline. Use [`ColorMode::Auto`] with
+    /// [`Output::StdErr`](crate::Output::StdErr) when printing it to stderr.
+    pub fn report(&self, mode: ColorMode) -> Report<'_> {
+        Report {
+            error: self,
+            color: mode.use_color(),
+            name: None,
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for SyntaxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<SyntaxError> for serde_json::Error {
+    fn from(error: SyntaxError) -> Self {
+        error.error
+    }
+}
+
+/// A report of a [`SyntaxError`], see [`SyntaxError::report`]
+pub struct Report<'a> {
+    error: &'a SyntaxError,
+    color: bool,
+    name: Option<&'a str>,
+}
+
+impl<'a> Report<'a> {
+    /// Show the name of the text in the location, e.g. the name of the file
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    fn paint<T>(&self, style: Style, item: T) -> Paint<T> {
+        match self.color {
+            true => Paint::new(item).with_style(style),
+            false => Paint::new(item),
+        }
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = self.error;
+        let accent = Style::new(Color::Red).bold();
+        let gutter = Style::new(Color::Blue).bold();
+
+        writeln!(
+            f,
+            "{} {}",
+            self.paint(accent, "error:"),
+            self.paint(Style::default().bold(), message(&error.error))
+        )?;
+
+        let Some((_, line)) = error.snippet.last() else {
+            return Ok(());
+        };
+
+        let width = error.line().to_string().len();
+        write!(f, "{:width$}{} ", "", self.paint(gutter, "-->"))?;
+        match self.name {
+            Some(name) => writeln!(f, "{name}:{}:{}", error.line(), error.column())?,
+            None => writeln!(f, "line {}, column {}", error.line(), error.column())?,
+        }
+        writeln!(f, "{:width$} {}", "", self.paint(gutter, "|"))?;
+        for (number, text) in &error.snippet {
+            writeln!(
+                f,
+                "{} {text}",
+                self.paint(gutter, format!("{number:>width$} |"))
+            )?;
+        }
+
+        // keep tabs, so that the marker lines up with the text
+        let mut end = error.column().saturating_sub(1).min(line.len());
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        let indent: String = line[..end]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(
+            f,
+            "{:width$} {} {indent}{}",
+            "",
+            self.paint(gutter, "|"),
+            self.paint(accent, "^")
+        )
+    }
+}
+
+/// The message of a parser error, without its location
+pub(crate) fn message(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    let location = format!(" at line {} column {}", error.line(), error.column());
+    match message.strip_suffix(&location) {
+        Some(message) => message.to_string(),
+        None => message,
+    }
+}
@@ -0,0 +1,290 @@
+use std::{fmt, io};
+use yansi::Style;
+
+/// The part of a JSON document a token belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Role {
+    /// `{` and `}`
+    ObjectBracket,
+    /// the `:` between a key and its value
+    Colon,
+    /// `[` and `]`
+    ArrayBracket,
+    /// an object key, or its quotes
+    Key,
+    /// a string value, or its quotes
+    String,
+    /// an escape sequence in a string or key, only if the styler has a style for them
+    Escape,
+    /// a number without fraction or exponent
+    Integer,
+    /// a number with fraction or exponent
+    Float,
+    /// the `true` value
+    True,
+    /// the `false` value
+    False,
+    /// the `null` value
+    Null,
+    /// invalid input, when highlighting leniently
+    Error,
+    /// a marker for elided content, when truncating
+    Elided,
+    /// an added line or value of a diff
+    Added,
+    /// a removed line or value of a diff
+    Removed,
+    /// a changed line or value of a diff
+    Changed,
+}
+
+impl Role {
+    pub(crate) fn bool(value: bool) -> Self {
+        if value {
+            Role::True
+        } else {
+            Role::False
+        }
+    }
+}
+
+/// A token to be highlighted, with the style the [`Styler`](crate::Styler) assigns to it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Highlight {
+    /// what the token is
+    pub role: Role,
+    /// the style of the token
+    pub style: Style,
+    /// the style comes from a [`PathRule`](crate::PathRule) or the palette of rainbow brackets,
+    /// instead of the style of the role
+    pub overridden: bool,
+}
+
+/// Marks up the tokens of colored output, like with ANSI escape codes or HTML elements
+///
+/// The [`ColoredFormatter`](crate::ColoredFormatter) decides what each token is and which style
+/// it gets, the highlighter decides how to write it. The text of a token is passed to
+/// [`Highlighter::write_text`], between [`Highlighter::begin_token`] and
+/// [`Highlighter::end_token`], which are not called for empty tokens. Whitespace and commas are
+/// written to the writer directly.
+///
+/// A string or key is written as multiple tokens: its quotes, fragments and escape sequences.
+/// These are enclosed by [`Highlighter::begin_string`] and [`Highlighter::end_string`], for
+/// marking up the string as a whole.
+///
+/// All methods have defaults, writing the text as it is.
+///
+/// ```rust
+/// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, Highlight, Highlighter, Role};
+/// # use serde_json::json;
+/// # use std::io;
+/// /// Writes keys in bold Markdown
+/// struct Markdown;
+///
+/// impl Highlighter for Markdown {
+///     fn begin_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+///     where
+///         W: ?Sized + io::Write,
+///     {
+///         match token.role {
+///             Role::Key => writer.write_all(b"**"),
+///             _ => Ok(()),
+///         }
+///     }
+///
+///     fn end_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+///     where
+///         W: ?Sized + io::Write,
+///     {
+///         self.begin_string(writer, token)
+///     }
+/// }
+///
+/// let s = ColoredFormatter::new(CompactFormatter {})
+///     .with_highlighter(Markdown)
+///     .to_colored_json(&json!({"a": "b"}), ColorMode::Off)?;
+/// assert_eq!(s, r#"{**"a"**:"b"}"#);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub trait Highlighter {
+    /// Write what goes before the text of a token
+    fn begin_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = (writer, token);
+        Ok(())
+    }
+
+    /// Write (a part of) the text of a token
+    fn write_text<W>(&mut self, writer: &mut W, text: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(text)
+    }
+
+    /// Write what goes after the text of a token
+    fn end_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = (writer, token);
+        Ok(())
+    }
+
+    /// Write what goes before a string or key, including its quotes
+    fn begin_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = (writer, token);
+        Ok(())
+    }
+
+    /// Write what goes after a string or key
+    fn end_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let _ = (writer, token);
+        Ok(())
+    }
+
+    /// Whether the output is meant for a terminal
+    ///
+    /// Output for a terminal only gets highlighted if the [`ColorMode`](crate::ColorMode) says so,
+    /// and its colors get downgraded to the color depth of the terminal. Other output is always
+    /// highlighted, using the colors as they are.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Highlights tokens with ANSI escape codes, the default highlighter
+#[derive(Clone, Default)]
+pub struct AnsiHighlighter {
+    cache: StyleCache,
+}
+
+impl Highlighter for AnsiHighlighter {
+    fn begin_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.cache.get(token.style).write_prefix(writer)
+    }
+
+    fn end_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        write_fmt(writer, |f| token.style.fmt_suffix(f))
+    }
+
+    fn is_terminal(&self) -> bool {
+        true
+    }
+}
+
+/// Capacity for a rendered prefix, the longest one `yansi` produces is about 50 bytes.
+const PREFIX_CAPACITY: usize = 64;
+
+/// The pre-rendered escape codes of a style.
+#[derive(Clone, Copy)]
+struct Codes {
+    style: Style,
+    prefix: [u8; PREFIX_CAPACITY],
+    /// length of the prefix, `None` if it didn't fit and must be rendered on the fly
+    len: Option<usize>,
+}
+
+impl Codes {
+    fn new(style: Style) -> Self {
+        struct Buffer<'a>(&'a mut [u8; PREFIX_CAPACITY], usize);
+
+        impl fmt::Write for Buffer<'_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let end = self.1 + s.len();
+                self.0
+                    .get_mut(self.1..end)
+                    .ok_or(fmt::Error)?
+                    .copy_from_slice(s.as_bytes());
+                self.1 = end;
+                Ok(())
+            }
+        }
+
+        let mut prefix = [0u8; PREFIX_CAPACITY];
+        let mut buffer = Buffer(&mut prefix, 0);
+        let len = style.fmt_prefix(&mut buffer).ok().map(|_| buffer.1);
+
+        Codes { style, prefix, len }
+    }
+
+    fn write_prefix<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        match self.len {
+            Some(len) => writer.write_all(&self.prefix[..len]),
+            None => write_fmt(writer, |f| self.style.fmt_prefix(f)),
+        }
+    }
+}
+
+/// A cache of rendered escape codes, so that styles don't need to be rendered for every token.
+#[derive(Clone, Default)]
+struct StyleCache {
+    entries: Vec<Codes>,
+}
+
+impl StyleCache {
+    fn get(&mut self, style: Style) -> &Codes {
+        let index = match self.entries.iter().position(|codes| codes.style == style) {
+            Some(index) => index,
+            None => {
+                self.entries.push(Codes::new(style));
+                self.entries.len() - 1
+            }
+        };
+        &self.entries[index]
+    }
+}
+
+/// Run a [`fmt::Write`] based function on an [`io::Write`], without buffering.
+fn write_fmt<W, F>(writer: &mut W, f: F) -> io::Result<()>
+where
+    W: ?Sized + io::Write,
+    F: FnOnce(&mut dyn fmt::Write) -> fmt::Result,
+{
+    struct Adapter<'a, W: ?Sized> {
+        writer: &'a mut W,
+        error: Option<io::Error>,
+    }
+
+    impl<W> fmt::Write for Adapter<'_, W>
+    where
+        W: ?Sized + io::Write,
+    {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.writer.write_all(s.as_bytes()).map_err(|err| {
+                self.error = Some(err);
+                fmt::Error
+            })
+        }
+    }
+
+    let mut adapter = Adapter {
+        writer,
+        error: None,
+    };
+
+    f(&mut adapter).map_err(|_| {
+        adapter
+            .error
+            .take()
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))
+    })
+}
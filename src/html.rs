@@ -0,0 +1,305 @@
+use crate::color::to_rgb;
+use crate::highlight::{Highlight, Highlighter, Role};
+use crate::Styler;
+use std::fmt::Write;
+use std::io;
+use yansi::Style;
+
+/// How HTML output gets styled, see [`HtmlHighlighter`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HtmlStyle {
+    /// Wrap tokens in elements like `<span class="json-key">`, styled by a stylesheet
+    ///
+    /// See [`Styler::to_css`] for creating the stylesheet. The styles of path rules and rainbow
+    /// brackets are not part of the styler, so they are added inline.
+    #[default]
+    Classes,
+    /// Wrap tokens in elements like `<span style="color: #0000ee">`, using the styles of the
+    /// styler
+    Inline,
+}
+
+/// Highlights tokens with HTML elements, escaping their text
+///
+/// Strings and keys get a single element, escape sequences in them a nested one.
+///
+/// ```rust
+/// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, HtmlHighlighter, HtmlStyle};
+/// # use serde_json::json;
+/// let html = ColoredFormatter::new(CompactFormatter {})
+///     .with_highlighter(HtmlHighlighter::new(HtmlStyle::Inline))
+///     .to_colored_json(&json!(["<b>"]), ColorMode::Off)?;
+///
+/// assert_eq!(
+///     html,
+///     "<span style=\"font-weight: bold;\">[</span>\
+///      <span style=\"color: #00cd00;\">&quot;&lt;b&gt;&quot;</span>\
+///      <span style=\"font-weight: bold;\">]</span>"
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct HtmlHighlighter {
+    style: HtmlStyle,
+    /// the rendered opening tags
+    tags: Vec<(Highlight, String)>,
+    /// the role of the current string
+    string: Option<Role>,
+}
+
+impl HtmlHighlighter {
+    pub fn new(style: HtmlStyle) -> Self {
+        HtmlHighlighter {
+            style,
+            tags: Vec::new(),
+            string: None,
+        }
+    }
+
+    /// The opening tag of an element for the token, empty if it doesn't need one
+    fn tag(&mut self, token: Highlight) -> &str {
+        let index = match self.tags.iter().position(|(t, _)| *t == token) {
+            Some(index) => index,
+            None => {
+                self.tags.push((token, open_tag(self.style, token)));
+                self.tags.len() - 1
+            }
+        };
+        &self.tags[index].1
+    }
+
+    fn open<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(self.tag(token).as_bytes())
+    }
+
+    fn close<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        match self.tag(token) {
+            "" => Ok(()),
+            _ => writer.write_all(b"</span>"),
+        }
+    }
+}
+
+impl Highlighter for HtmlHighlighter {
+    fn begin_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        // the parts of a string are in the element of the string
+        match self.string == Some(token.role) {
+            true => Ok(()),
+            false => self.open(writer, token),
+        }
+    }
+
+    fn write_text<W>(&mut self, writer: &mut W, text: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        write_escaped(writer, text)
+    }
+
+    fn end_token<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        match self.string == Some(token.role) {
+            true => Ok(()),
+            false => self.close(writer, token),
+        }
+    }
+
+    fn begin_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.string = Some(token.role);
+        self.open(writer, token)
+    }
+
+    fn end_string<W>(&mut self, writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.string = None;
+        self.close(writer, token)
+    }
+}
+
+impl Role {
+    /// The CSS class of the role
+    fn class(self) -> &'static str {
+        match self {
+            Role::ObjectBracket => "json-object-bracket",
+            Role::Colon => "json-colon",
+            Role::ArrayBracket => "json-array-bracket",
+            Role::Key => "json-key",
+            Role::String => "json-string",
+            Role::Escape => "json-escape",
+            Role::Integer => "json-integer",
+            Role::Float => "json-float",
+            Role::True => "json-true",
+            Role::False => "json-false",
+            Role::Null => "json-null",
+            Role::Error => "json-error",
+            Role::Elided => "json-elided",
+            Role::Added => "json-added",
+            Role::Removed => "json-removed",
+            Role::Changed => "json-changed",
+        }
+    }
+
+    /// The CSS classes of an element of the role
+    fn classes(self) -> &'static str {
+        match self {
+            Role::True => "json-bool json-true",
+            Role::False => "json-bool json-false",
+            role => role.class(),
+        }
+    }
+}
+
+impl Styler {
+    /// A stylesheet for HTML output using [`HtmlStyle::Classes`]
+    ///
+    /// Only classes with a visible style get a rule.
+    ///
+    /// ```rust
+    /// # use colored_json::Styler;
+    /// let css = Styler::default().to_css();
+    /// assert!(css.contains(".json-key { color: #0000ee; font-weight: bold; }"));
+    /// ```
+    pub fn to_css(&self) -> String {
+        let rules = [
+            (Role::ObjectBracket.class(), Some(self.object_brackets)),
+            (Role::Colon.class(), Some(self.object_colon)),
+            (Role::ArrayBracket.class(), Some(self.array_brackets)),
+            (Role::Key.class(), Some(self.key)),
+            (Role::String.class(), Some(self.string_value)),
+            (Role::Escape.class(), self.escape),
+            (Role::Integer.class(), Some(self.integer_value)),
+            (Role::Float.class(), Some(self.float_value)),
+            // bool values carry both classes, so this must come first
+            ("json-bool", Some(self.bool_value)),
+            (Role::True.class(), self.true_value),
+            (Role::False.class(), self.false_value),
+            (Role::Null.class(), Some(self.nil_value)),
+            (Role::Error.class(), Some(self.error_style())),
+            (Role::Elided.class(), Some(self.elided_style())),
+            (Role::Added.class(), Some(self.added)),
+            (Role::Removed.class(), Some(self.removed)),
+            (Role::Changed.class(), Some(self.changed)),
+        ];
+
+        let mut css = String::new();
+        for (class, style) in rules {
+            let Some(style) = style else {
+                continue;
+            };
+            let declarations = declarations(style);
+            if !declarations.is_empty() {
+                let _ = writeln!(css, ".{class} {{ {declarations} }}");
+            }
+        }
+        css
+    }
+}
+
+/// The CSS declarations of a style, empty for the default style
+fn declarations(style: Style) -> String {
+    let mut fg = to_rgb(style.fg_color());
+    let mut bg = to_rgb(style.bg_color());
+    if style.is_invert() {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+
+    let mut css = String::new();
+    let mut push = |declaration: &str| {
+        if !css.is_empty() {
+            css.push(' ');
+        }
+        css.push_str(declaration);
+        css.push(';');
+    };
+
+    if let Some((r, g, b)) = fg {
+        push(&format!("color: #{r:02x}{g:02x}{b:02x}"));
+    }
+    if let Some((r, g, b)) = bg {
+        push(&format!("background-color: #{r:02x}{g:02x}{b:02x}"));
+    }
+    if style.is_bold() {
+        push("font-weight: bold");
+    }
+    if style.is_dimmed() {
+        push("opacity: 0.5");
+    }
+    if style.is_italic() {
+        push("font-style: italic");
+    }
+
+    let decorations: Vec<_> = [
+        (style.is_underline(), "underline"),
+        (style.is_strikethrough(), "line-through"),
+        (style.is_blink(), "blink"),
+    ]
+    .into_iter()
+    .filter_map(|(set, decoration)| set.then_some(decoration))
+    .collect();
+    if !decorations.is_empty() {
+        push(&format!("text-decoration: {}", decorations.join(" ")));
+    }
+
+    if style.is_hidden() {
+        push("visibility: hidden");
+    }
+    css
+}
+
+/// The opening tag of an element for a token, empty if it doesn't need one
+///
+/// With classes, a style overriding the style of the role is added inline.
+fn open_tag(html: HtmlStyle, token: Highlight) -> String {
+    let declarations = match html {
+        HtmlStyle::Classes if !token.overridden => String::new(),
+        _ => declarations(token.style),
+    };
+    let classes = token.role.classes();
+
+    match html {
+        HtmlStyle::Classes if declarations.is_empty() => format!("<span class=\"{classes}\">"),
+        HtmlStyle::Classes => {
+            format!("<span class=\"{classes}\" style=\"{declarations}\">")
+        }
+        HtmlStyle::Inline if declarations.is_empty() => String::new(),
+        HtmlStyle::Inline => format!("<span style=\"{declarations}\">"),
+    }
+}
+
+/// Write text, escaping the characters with a special meaning in HTML
+fn write_escaped<W>(writer: &mut W, text: &[u8]) -> io::Result<()>
+where
+    W: ?Sized + io::Write,
+{
+    let mut start = 0;
+    for (i, byte) in text.iter().enumerate() {
+        let entity: &[u8] = match byte {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            b'>' => b"&gt;",
+            b'"' => b"&quot;",
+            b'\'' => b"&#39;",
+            _ => continue,
+        };
+        writer.write_all(&text[start..i])?;
+        writer.write_all(entity)?;
+        start = i + 1;
+    }
+    writer.write_all(&text[start..])
+}
@@ -0,0 +1,393 @@
+use crate::sort::Node;
+use crate::{ColorMode, ColoredFormatter, Styler};
+use serde::de::IgnoredAny;
+use serde::Serialize;
+use serde_json::ser::{Formatter, PrettyFormatter};
+use std::io;
+
+/// The number of columns a tab is counted as, when fitting containers into the maximum width
+const TAB_WIDTH: usize = 8;
+
+/// The indentation of pretty printed JSON
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// indent with a number of spaces per level
+    Spaces(usize),
+    /// indent with a tab per level
+    Tab,
+}
+
+impl Indent {
+    fn bytes(self) -> Vec<u8> {
+        match self {
+            Indent::Spaces(n) => vec![b' '; n],
+            Indent::Tab => vec![b'\t'],
+        }
+    }
+
+    fn width(self) -> usize {
+        match self {
+            Indent::Spaces(n) => n,
+            Indent::Tab => TAB_WIDTH,
+        }
+    }
+}
+
+/// Options for pretty printing colored JSON
+///
+/// By default, every value of an array or object is written on its own line, like by
+/// [`PrettyFormatter`]. With a maximum width, arrays and objects which fit into the line are
+/// kept on it.
+///
+/// ```rust
+/// # use colored_json::{to_colored_json_with_options, ColorMode, FormatOptions, Indent};
+/// # use serde_json::json;
+/// let options = FormatOptions::default()
+///     .with_indent(Indent::Spaces(4))
+///     .with_max_width(50);
+///
+/// let s = to_colored_json_with_options(
+///     &json!({"point": {"x": 1, "y": 2}, "tags": ["a", "b"]}),
+///     ColorMode::Off,
+///     &options,
+/// )?;
+/// assert_eq!(s, "{\"point\": {\"x\": 1, \"y\": 2}, \"tags\": [\"a\", \"b\"]}");
+///
+/// let s = to_colored_json_with_options(
+///     &json!({"point": {"x": 1, "y": 2}, "tags": ["alpha", "beta", "gamma"]}),
+///     ColorMode::Off,
+///     &options,
+/// )?;
+/// assert_eq!(
+///     s,
+///     "{\n    \"point\": {\"x\": 1, \"y\": 2},\n    \"tags\": [\"alpha\", \"beta\", \"gamma\"]\n}"
+/// );
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    styler: Styler,
+    indent: Indent,
+    max_width: Option<usize>,
+    sort_keys: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            styler: Styler::default(),
+            indent: Indent::Spaces(2),
+            max_width: None,
+            sort_keys: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Set the styler to color the output with
+    pub fn with_styler(mut self, styler: Styler) -> Self {
+        self.styler = styler;
+        self
+    }
+
+    /// Set the indentation, defaults to two spaces
+    pub fn with_indent(mut self, indent: Indent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Keep arrays and objects on one line, if they fit into the width
+    ///
+    /// The width is counted in characters, with a tab counting as eight. Containers which don't
+    /// fit are broken up, one value per line, as far as needed. Lines may still exceed the width,
+    /// e.g. for long strings.
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Sort the keys of all objects, see [`ColoredFormatter::with_sort_keys`]
+    pub fn with_sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    pub(crate) fn write_value<W, T>(
+        &self,
+        value: &T,
+        writer: &mut W,
+        mode: ColorMode,
+    ) -> serde_json::Result<()>
+    where
+        W: io::Write,
+        T: Serialize,
+    {
+        if self.max_width.is_some() {
+            // the layout depends on the text of the values
+            return self.write_str(&serde_json::to_string(value)?, writer, mode);
+        }
+
+        let indent = self.indent.bytes();
+        ColoredFormatter::with_styler(PrettyFormatter::with_indent(&indent), self.styler)
+            .with_sort_keys(self.sort_keys)
+            .write_colored_json(value, writer, mode)
+    }
+
+    pub(crate) fn write_str<W>(
+        &self,
+        json: &str,
+        writer: &mut W,
+        mode: ColorMode,
+    ) -> serde_json::Result<()>
+    where
+        W: io::Write,
+    {
+        let indent = self.indent.bytes();
+        let Some(max_width) = self.max_width else {
+            return ColoredFormatter::with_styler(
+                PrettyFormatter::with_indent(&indent),
+                self.styler,
+            )
+            .with_sort_keys(self.sort_keys)
+            .write_colored_str(json, writer, mode);
+        };
+
+        serde_json::from_str::<IgnoredAny>(json)?;
+        let mut compact = String::with_capacity(json.len());
+        let mut inline = Vec::new();
+        if let Some(node) = Node::parse(json, self.sort_keys) {
+            node.write(&mut compact);
+            plan(&node, 0, 0, 0, self.indent.width(), max_width, &mut inline);
+        }
+
+        let formatter = LayoutFormatter {
+            indent,
+            current_indent: 0,
+            has_value: false,
+            inline: inline.into_iter(),
+            stack: Vec::new(),
+        };
+        ColoredFormatter::with_styler(formatter, self.styler)
+            .write_colored_str(&compact, writer, mode)
+    }
+}
+
+/// Decide for each array and object, in the order they are written, if it is kept on one line
+///
+/// `column` is where the node starts, `trailing` the width of what follows it on the line.
+fn plan(
+    node: &Node<'_>,
+    column: usize,
+    trailing: usize,
+    depth: usize,
+    indent: usize,
+    max_width: usize,
+    inline: &mut Vec<bool>,
+) {
+    if let Node::Scalar(_) = node {
+        return;
+    }
+    if column + width(node) + trailing <= max_width {
+        keep_inline(node, inline);
+        return;
+    }
+
+    inline.push(false);
+    let column = (depth + 1) * indent;
+    match node {
+        Node::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                let trailing = usize::from(i + 1 < values.len());
+                plan(
+                    value,
+                    column,
+                    trailing,
+                    depth + 1,
+                    indent,
+                    max_width,
+                    inline,
+                );
+            }
+        }
+        Node::Object(entries) => {
+            for (i, (key, _, value)) in entries.iter().enumerate() {
+                let trailing = usize::from(i + 1 < entries.len());
+                let column = column + key.chars().count() + 2;
+                plan(
+                    value,
+                    column,
+                    trailing,
+                    depth + 1,
+                    indent,
+                    max_width,
+                    inline,
+                );
+            }
+        }
+        Node::Scalar(_) => {}
+    }
+}
+
+/// Keep the node, and everything nested in it, on one line
+fn keep_inline(node: &Node<'_>, inline: &mut Vec<bool>) {
+    match node {
+        Node::Scalar(_) => {}
+        Node::Array(values) => {
+            inline.push(true);
+            values.iter().for_each(|value| keep_inline(value, inline));
+        }
+        Node::Object(entries) => {
+            inline.push(true);
+            entries
+                .iter()
+                .for_each(|(_, _, value)| keep_inline(value, inline));
+        }
+    }
+}
+
+/// The width of the node, written on one line
+fn width(node: &Node<'_>) -> usize {
+    // separated by `, `, and `: ` between keys and values
+    let separators = |n: usize| n.saturating_sub(1) * 2;
+    match node {
+        Node::Scalar(token) => token.chars().count(),
+        Node::Array(values) => {
+            2 + values.iter().map(width).sum::<usize>() + separators(values.len())
+        }
+        Node::Object(entries) => {
+            2 + entries
+                .iter()
+                .map(|(key, _, value)| key.chars().count() + 2 + width(value))
+                .sum::<usize>()
+                + separators(entries.len())
+        }
+    }
+}
+
+/// Like [`PrettyFormatter`], but keeps the arrays and objects of the plan on one line
+struct LayoutFormatter {
+    indent: Vec<u8>,
+    current_indent: usize,
+    has_value: bool,
+    /// for each array and object, if it is kept on one line
+    inline: std::vec::IntoIter<bool>,
+    /// if the open arrays and objects are kept on one line
+    stack: Vec<bool>,
+}
+
+impl LayoutFormatter {
+    fn begin<W>(&mut self, writer: &mut W, bracket: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let inline = self.inline.next().unwrap_or(false);
+        self.stack.push(inline);
+        self.current_indent += 1;
+        self.has_value = false;
+        writer.write_all(bracket)
+    }
+
+    fn end<W>(&mut self, writer: &mut W, bracket: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let inline = self.stack.pop().unwrap_or(false);
+        self.current_indent -= 1;
+        if self.has_value && !inline {
+            writer.write_all(b"\n")?;
+            self.write_indent(writer)?;
+        }
+        writer.write_all(bracket)
+    }
+
+    fn begin_entry<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        match (self.stack.last().copied().unwrap_or(false), first) {
+            (true, true) => Ok(()),
+            (true, false) => writer.write_all(b", "),
+            (false, first) => {
+                writer.write_all(if first { b"\n" } else { b",\n" })?;
+                self.write_indent(writer)
+            }
+        }
+    }
+
+    fn write_indent<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        for _ in 0..self.current_indent {
+            writer.write_all(&self.indent)?;
+        }
+        Ok(())
+    }
+}
+
+impl Formatter for LayoutFormatter {
+    fn begin_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin(writer, b"[")
+    }
+
+    fn end_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.end(writer, b"]")
+    }
+
+    fn begin_array_value<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_entry(writer, first)
+    }
+
+    fn end_array_value<W>(&mut self, _writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin(writer, b"{")
+    }
+
+    fn end_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.end(writer, b"}")
+    }
+
+    fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_entry(writer, first)
+    }
+
+    fn begin_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b": ")
+    }
+
+    fn end_object_value<W>(&mut self, _writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.has_value = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,426 @@
+use crate::{Highlight, Highlighter, Role};
+use std::cell::RefCell;
+use std::io;
+
+/// The kind of a JSON token
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// `{`
+    BeginObject,
+    /// `}`
+    EndObject,
+    /// `[`
+    BeginArray,
+    /// `]`
+    EndArray,
+    /// the `:` between a key and its value
+    Colon,
+    /// the `,` between values
+    Comma,
+    /// an object key, including the quotes
+    Key,
+    /// a string value, including the quotes
+    String,
+    /// a number without fraction or exponent
+    Integer,
+    /// a number with fraction or exponent
+    Float,
+    /// `true` or `false`
+    Bool,
+    /// `null`
+    Null,
+    /// spaces, tabs and line breaks
+    Whitespace,
+    /// Input which is not valid JSON
+    Error,
+}
+
+/// A lexer, splitting JSON text into tokens
+///
+/// The lexer keeps track of the nesting, so that it can tell object keys from string values. It
+/// does not validate the structure of the document, but reports input which cannot be a JSON
+/// token as [`TokenKind::Error`]. All input is covered by the tokens, including whitespace.
+///
+/// ```rust
+/// # use colored_json::{Lexer, TokenKind};
+/// let tokens: Vec<_> = Lexer::new(r#"{"a": 1}"#).collect();
+/// assert_eq!(
+///     tokens,
+///     [
+///         (TokenKind::BeginObject, "{"),
+///         (TokenKind::Key, "\"a\""),
+///         (TokenKind::Colon, ":"),
+///         (TokenKind::Whitespace, " "),
+///         (TokenKind::Integer, "1"),
+///         (TokenKind::EndObject, "}"),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    /// the open containers, `true` for objects
+    stack: Vec<bool>,
+    expect_key: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            pos: 0,
+            stack: Vec::new(),
+            expect_key: false,
+        }
+    }
+
+    fn next_token(&self) -> (TokenKind, usize) {
+        let bytes = &self.input.as_bytes()[self.pos..];
+        match bytes[0] {
+            b'{' => (TokenKind::BeginObject, 1),
+            b'}' => (TokenKind::EndObject, 1),
+            b'[' => (TokenKind::BeginArray, 1),
+            b']' => (TokenKind::EndArray, 1),
+            b':' => (TokenKind::Colon, 1),
+            b',' => (TokenKind::Comma, 1),
+            b' ' | b'\t' | b'\n' | b'\r' => (
+                TokenKind::Whitespace,
+                bytes
+                    .iter()
+                    .position(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+                    .unwrap_or(bytes.len()),
+            ),
+            b'"' => match scan_string(bytes) {
+                Some(len) if self.expect_key => (TokenKind::Key, len),
+                Some(len) => (TokenKind::String, len),
+                None => (TokenKind::Error, bytes.len()),
+            },
+            b'-' | b'0'..=b'9' => scan_number(bytes),
+            _ => {
+                let len = bytes
+                    .iter()
+                    .position(|b| !b.is_ascii_alphanumeric())
+                    .unwrap_or(bytes.len())
+                    .max(1);
+                match &bytes[..len] {
+                    b"true" | b"false" => (TokenKind::Bool, len),
+                    b"null" => (TokenKind::Null, len),
+                    // keep the error on a char boundary
+                    _ => (TokenKind::Error, self.char_len(len)),
+                }
+            }
+        }
+    }
+
+    fn char_len(&self, len: usize) -> usize {
+        let rest = &self.input[self.pos..];
+        let mut end = len;
+        while !rest.is_char_boundary(end) {
+            end += 1;
+        }
+        end
+    }
+
+    fn track(&mut self, kind: TokenKind) {
+        match kind {
+            TokenKind::BeginObject => {
+                self.stack.push(true);
+                self.expect_key = true;
+            }
+            TokenKind::BeginArray => {
+                self.stack.push(false);
+                self.expect_key = false;
+            }
+            TokenKind::EndObject | TokenKind::EndArray => {
+                self.stack.pop();
+                self.expect_key = false;
+            }
+            TokenKind::Comma => {
+                self.expect_key = self.stack.last().copied().unwrap_or(false);
+            }
+            TokenKind::Whitespace => {}
+            _ => self.expect_key = false,
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = (TokenKind, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.input.len() {
+            return None;
+        }
+
+        let (kind, len) = self.next_token();
+        let token = &self.input[self.pos..self.pos + len];
+        self.pos += len;
+        self.track(kind);
+
+        Some((kind, token))
+    }
+}
+
+/// Scan a string, starting with the opening quote, returns the length including the quotes
+fn scan_string(bytes: &[u8]) -> Option<usize> {
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return Some(i + 1),
+            b'\\' => i += 2,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Scan a number, reporting anything which doesn't follow the JSON grammar as error
+fn scan_number(bytes: &[u8]) -> (TokenKind, usize) {
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .position(|b| !b.is_ascii_digit())
+            .unwrap_or(bytes.len() - from)
+    };
+
+    let mut i = usize::from(bytes[0] == b'-');
+    let mut valid = true;
+    let mut float = false;
+
+    match digits(i) {
+        0 => valid = false,
+        n => {
+            valid &= bytes[i] != b'0' || n == 1;
+            i += n;
+        }
+    }
+
+    if bytes.get(i) == Some(&b'.') {
+        float = true;
+        let n = digits(i + 1);
+        valid &= n > 0;
+        i += 1 + n;
+    }
+
+    if let Some(b'e' | b'E') = bytes.get(i) {
+        float = true;
+        i += 1;
+        if let Some(b'+' | b'-') = bytes.get(i) {
+            i += 1;
+        }
+        let n = digits(i);
+        valid &= n > 0;
+        i += n;
+    }
+
+    // trailing garbage, like in `12ab`, belongs to the number
+    let garbage = bytes[i..]
+        .iter()
+        .position(|b| !b.is_ascii_alphanumeric() && *b != b'.')
+        .unwrap_or(bytes.len() - i);
+
+    match (valid && garbage == 0, float) {
+        (true, true) => (TokenKind::Float, i),
+        (true, false) => (TokenKind::Integer, i),
+        (false, _) => (TokenKind::Error, i + garbage),
+    }
+}
+
+/// The offset of the last token of the input, which is not whitespace
+pub(crate) fn last_token_offset(input: &str) -> Option<usize> {
+    let mut offset = 0;
+    let mut last = None;
+    for (kind, token) in Lexer::new(input) {
+        if kind != TokenKind::Whitespace {
+            last = Some(offset);
+        }
+        offset += token.len();
+    }
+    last
+}
+
+/// Check if a number, in its textual representation, is a float
+pub(crate) fn is_float(number: &str) -> bool {
+    number.bytes().any(|b| matches!(b, b'.' | b'e' | b'E'))
+}
+
+/// A part of the content of a string token
+pub(crate) enum StringPart<'a> {
+    Fragment(&'a str),
+    Escape(&'a str),
+}
+
+/// Split the content of a string token (without the quotes) into fragments and escapes
+pub(crate) fn string_parts(content: &str) -> impl Iterator<Item = StringPart<'_>> {
+    let mut rest = content;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let part = match rest.find('\\') {
+            Some(0) => {
+                let len = match rest.as_bytes().get(1) {
+                    Some(b'u') => 6,
+                    Some(_) => 2,
+                    None => 1,
+                };
+                let mut len = len.min(rest.len());
+                while !rest.is_char_boundary(len) {
+                    len += 1;
+                }
+                let (escape, tail) = rest.split_at(len);
+                rest = tail;
+                StringPart::Escape(escape)
+            }
+            Some(n) => {
+                let (fragment, tail) = rest.split_at(n);
+                rest = tail;
+                StringPart::Fragment(fragment)
+            }
+            None => StringPart::Fragment(std::mem::take(&mut rest)),
+        };
+        Some(part)
+    })
+}
+
+/// Passes tokens to a visitor, joining whitespace which was written in parts
+pub(crate) struct TokenSink<V> {
+    visitor: V,
+    whitespace: String,
+}
+
+impl<V> TokenSink<V>
+where
+    V: FnMut(TokenKind, &str),
+{
+    pub(crate) fn new(visitor: V) -> Self {
+        TokenSink {
+            visitor,
+            whitespace: String::new(),
+        }
+    }
+
+    fn visit(&mut self, kind: TokenKind, token: &str) {
+        if kind == TokenKind::Whitespace {
+            self.whitespace.push_str(token);
+            return;
+        }
+        self.flush();
+        (self.visitor)(kind, token);
+    }
+
+    fn visit_lexed(&mut self, text: &str) {
+        for (kind, token) in Lexer::new(text) {
+            self.visit(kind, token);
+        }
+    }
+
+    /// Pass the pending whitespace to the visitor
+    pub(crate) fn flush(&mut self) {
+        if !self.whitespace.is_empty() {
+            (self.visitor)(TokenKind::Whitespace, &self.whitespace);
+            self.whitespace.clear();
+        }
+    }
+}
+
+/// A writer, passing what the formatter writes between the tokens to the sink
+///
+/// This is only ever commas and whitespace, the tokens go through the [`TokenForwarder`].
+pub(crate) struct Separators<'a, V>(pub(crate) &'a RefCell<TokenSink<V>>);
+
+impl<V> io::Write for Separators<'_, V>
+where
+    V: FnMut(TokenKind, &str),
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .borrow_mut()
+            .visit_lexed(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A highlighter, passing the tokens of the [`ColoredFormatter`](crate::ColoredFormatter) to the
+/// sink, instead of writing them
+///
+/// Strings and keys are passed as a whole, once complete. Brackets and colons may carry the
+/// whitespace of the formatter, so their text is split by the [`Lexer`].
+pub(crate) struct TokenForwarder<'a, V> {
+    sink: &'a RefCell<TokenSink<V>>,
+    role: Role,
+    /// the kind and text of the string being written
+    string: Option<(TokenKind, String)>,
+}
+
+impl<'a, V> TokenForwarder<'a, V> {
+    pub(crate) fn new(sink: &'a RefCell<TokenSink<V>>) -> Self {
+        TokenForwarder {
+            sink,
+            role: Role::Error,
+            string: None,
+        }
+    }
+}
+
+impl<V> Highlighter for TokenForwarder<'_, V>
+where
+    V: FnMut(TokenKind, &str),
+{
+    fn begin_token<W>(&mut self, _writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.role = token.role;
+        Ok(())
+    }
+
+    fn write_text<W>(&mut self, _writer: &mut W, text: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let text = String::from_utf8_lossy(text);
+        if let Some((_, string)) = &mut self.string {
+            string.push_str(&text);
+            return Ok(());
+        }
+
+        let mut sink = self.sink.borrow_mut();
+        match self.role {
+            Role::Integer => sink.visit(TokenKind::Integer, &text),
+            Role::Float => sink.visit(TokenKind::Float, &text),
+            Role::True | Role::False => sink.visit(TokenKind::Bool, &text),
+            Role::Null => sink.visit(TokenKind::Null, &text),
+            _ => sink.visit_lexed(&text),
+        }
+        Ok(())
+    }
+
+    fn begin_string<W>(&mut self, _writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let kind = match token.role {
+            Role::Key => TokenKind::Key,
+            _ => TokenKind::String,
+        };
+        self.string = Some((kind, String::new()));
+        Ok(())
+    }
+
+    fn end_string<W>(&mut self, _writer: &mut W, _token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if let Some((kind, string)) = self.string.take() {
+            self.sink.borrow_mut().visit(kind, &string);
+        }
+        Ok(())
+    }
+}
@@ -125,14 +125,57 @@
 //!    # }
 //!```
 
+use serde::de::IgnoredAny;
 use serde::Serialize;
-use serde_json::ser::Formatter;
+use serde_json::ser::{CharEscape, Formatter};
 pub use serde_json::ser::{CompactFormatter, PrettyFormatter};
-use serde_json::value::Value;
-use std::io::{self, IsTerminal};
+use std::cell::RefCell;
+use std::io::{self, Write};
 
 pub use yansi::{Color, Style};
 
+mod color;
+mod config;
+pub mod diff;
+mod env;
+mod error;
+mod highlight;
+mod html;
+mod layout;
+mod lexer;
+mod path;
+mod shaper;
+mod sort;
+mod stream;
+mod styled;
+#[cfg(feature = "tracing")]
+mod subscriber;
+mod theme;
+mod truncate;
+#[cfg(feature = "ratatui")]
+mod tui;
+
+pub use color::ColorDepth;
+pub use config::{parse_style, StyleError, JQ_COLORS};
+pub use env::{Environment, ProcessEnvironment};
+pub use error::{Report, SyntaxError};
+pub use highlight::{AnsiHighlighter, Highlight, Highlighter, Role};
+pub use html::{HtmlHighlighter, HtmlStyle};
+pub use layout::{FormatOptions, Indent};
+pub use lexer::{Lexer, TokenKind};
+use lexer::{Separators, StringPart, TokenForwarder, TokenSink};
+pub use path::PathRule;
+use path::PathTracker;
+use shaper::Shaper;
+pub use stream::{LineError, StreamColorizer};
+use styled::{Painter, StyledWriter};
+#[cfg(feature = "tracing")]
+pub use subscriber::{ColoredJson, JsonFields, JsonVisitor};
+pub use theme::{Background, Theme};
+use truncate::Limits;
+#[cfg(feature = "ratatui")]
+pub use tui::to_ratatui_style;
+
 /// Enable ANSI support (on Windows).
 ///
 /// On Windows, the terminal needs to be put into an "ANSI mode" so that it will render colors.
@@ -160,7 +203,10 @@ pub mod prelude {
 }
 
 /// Styler lets you define the look of the colored json output
-#[derive(Clone, Copy)]
+///
+/// Instead of defining all styles yourself, you can also start with one of the built-in themes,
+/// see [`Styler::from_theme`].
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Styler {
     /// style of object brackets
     pub object_brackets: Style,
@@ -178,8 +224,24 @@ pub struct Styler {
     pub float_value: Style,
     /// style of bool values
     pub bool_value: Style,
+    /// style of the `true` value, `None` to use `bool_value`
+    pub true_value: Option<Style>,
+    /// style of the `false` value, `None` to use `bool_value`
+    pub false_value: Option<Style>,
     /// style of the `nil` value
     pub nil_value: Style,
+    /// style of escape sequences in strings and keys, `None` to use the style of the string
+    pub escape: Option<Style>,
+    /// style of invalid input, when highlighting leniently, `None` to use a red background
+    pub error: Option<Style>,
+    /// style of elided content, when truncating, `None` to use a dimmed style
+    pub elided: Option<Style>,
+    /// style of added values in a diff, see [`diff`]
+    pub added: Style,
+    /// style of removed values in a diff
+    pub removed: Style,
+    /// style of changed values in a diff
+    pub changed: Style,
     /// should the quotation get the style of the inner string/key?
     pub string_include_quotation: bool,
 }
@@ -196,21 +258,202 @@ impl Default for Styler {
             integer_value: Style::default(),
             float_value: Style::default(),
             bool_value: Style::default(),
+            true_value: None,
+            false_value: None,
             nil_value: Style::default(),
+            escape: None,
+            error: None,
+            elided: None,
+            added: Style::new(Color::Green),
+            removed: Style::new(Color::Red),
+            changed: Style::new(Color::Yellow),
             string_include_quotation: true,
         }
     }
 }
 
+impl Styler {
+    /// A default palette for rainbow brackets
+    ///
+    /// See [`ColoredFormatter::with_rainbow_brackets`].
+    pub fn rainbow_palette() -> Vec<Style> {
+        [
+            Color::Yellow,
+            Color::Magenta,
+            Color::Cyan,
+            Color::Green,
+            Color::Blue,
+            Color::Red,
+        ]
+        .into_iter()
+        .map(|color| Style::new(color).bold())
+        .collect()
+    }
+
+    /// Set the style of object brackets
+    pub fn with_object_brackets(mut self, style: Style) -> Self {
+        self.object_brackets = style;
+        self
+    }
+
+    /// Set the style of object colons
+    pub fn with_object_colon(mut self, style: Style) -> Self {
+        self.object_colon = style;
+        self
+    }
+
+    /// Set the style of array brackets
+    pub fn with_array_brackets(mut self, style: Style) -> Self {
+        self.array_brackets = style;
+        self
+    }
+
+    /// Set the style of object keys
+    pub fn with_key(mut self, style: Style) -> Self {
+        self.key = style;
+        self
+    }
+
+    /// Set the style of string values
+    pub fn with_string_value(mut self, style: Style) -> Self {
+        self.string_value = style;
+        self
+    }
+
+    /// Set the style of integer values
+    pub fn with_integer_value(mut self, style: Style) -> Self {
+        self.integer_value = style;
+        self
+    }
+
+    /// Set the style of float values
+    pub fn with_float_value(mut self, style: Style) -> Self {
+        self.float_value = style;
+        self
+    }
+
+    /// Set the style of bool values
+    pub fn with_bool_value(mut self, style: Style) -> Self {
+        self.bool_value = style;
+        self
+    }
+
+    /// Set the style of the `null` value
+    pub fn with_nil_value(mut self, style: Style) -> Self {
+        self.nil_value = style;
+        self
+    }
+
+    /// Set if the quotation marks get the style of the string or key they enclose
+    pub fn with_string_include_quotation(mut self, include: bool) -> Self {
+        self.string_include_quotation = include;
+        self
+    }
+
+    /// Set the style of the `true` value
+    ///
+    /// ```rust
+    /// # use colored_json::{Color, Style, Styler};
+    /// let styler = Styler::default()
+    ///     .with_true_value(Style::new(Color::Green))
+    ///     .with_false_value(Style::new(Color::Red));
+    /// ```
+    pub fn with_true_value(mut self, style: Style) -> Self {
+        self.true_value = Some(style);
+        self
+    }
+
+    /// Set the style of the `false` value
+    pub fn with_false_value(mut self, style: Style) -> Self {
+        self.false_value = Some(style);
+        self
+    }
+
+    /// Set the style of escape sequences in strings and keys
+    ///
+    /// ```rust
+    /// # use colored_json::{Color, Style, Styler};
+    /// let styler = Styler::default().with_escape(Style::new(Color::Red).bold());
+    /// ```
+    pub fn with_escape(mut self, style: Style) -> Self {
+        self.escape = Some(style);
+        self
+    }
+
+    /// Set the style of invalid input, see [`ColoredFormatter::write_colored_str_lenient`]
+    pub fn with_error(mut self, style: Style) -> Self {
+        self.error = Some(style);
+        self
+    }
+
+    /// Set the style of elided content, see [`ColoredFormatter::with_max_string_length`]
+    pub fn with_elided(mut self, style: Style) -> Self {
+        self.elided = Some(style);
+        self
+    }
+
+    /// Set the style of added values in a diff, see [`diff`]
+    pub fn with_added(mut self, style: Style) -> Self {
+        self.added = style;
+        self
+    }
+
+    /// Set the style of removed values in a diff
+    pub fn with_removed(mut self, style: Style) -> Self {
+        self.removed = style;
+        self
+    }
+
+    /// Set the style of changed values in a diff
+    pub fn with_changed(mut self, style: Style) -> Self {
+        self.changed = style;
+        self
+    }
+
+    /// The style of invalid input, falling back to a red background
+    pub fn error_style(&self) -> Style {
+        self.error
+            .unwrap_or_else(|| Style::new(Color::White).bg(Color::Red))
+    }
+
+    /// The style of elided content, falling back to a dimmed style
+    pub fn elided_style(&self) -> Style {
+        self.elided.unwrap_or_else(|| Style::default().dimmed())
+    }
+
+    /// The style of a bool value, falling back to `bool_value`
+    pub fn bool_style(&self, value: bool) -> Style {
+        let style = if value {
+            self.true_value
+        } else {
+            self.false_value
+        };
+        style.unwrap_or(self.bool_value)
+    }
+}
+
 /// `ColoredFormatter` decorates a `Formatter` with color defined in `Styler`
+///
+/// The colors are written as ANSI escape codes, unless a different [`Highlighter`] is used, see
+/// [`ColoredFormatter::with_highlighter`].
 #[derive(Clone)]
-pub struct ColoredFormatter<F>
+pub struct ColoredFormatter<F, H = AnsiHighlighter>
 where
     F: Formatter,
 {
     formatter: F,
     styler: Styler,
     in_object_key: bool,
+    painter: Painter<H>,
+    color_depth: Option<ColorDepth>,
+    rainbow: Vec<Style>,
+    depth: usize,
+    paths: PathTracker,
+    /// the next string is an object key, which must be captured to track its path
+    key_pending: bool,
+    original_whitespace: bool,
+    sort_keys: bool,
+    limits: Limits,
 }
 
 impl<F> ColoredFormatter<F>
@@ -222,6 +465,15 @@ where
             formatter,
             styler: Styler::default(),
             in_object_key: false,
+            painter: Painter::new(AnsiHighlighter::default()),
+            color_depth: None,
+            rainbow: Vec::new(),
+            depth: 0,
+            paths: PathTracker::default(),
+            key_pending: false,
+            original_whitespace: false,
+            sort_keys: false,
+            limits: Limits::default(),
         }
     }
 
@@ -230,6 +482,226 @@ where
             formatter,
             styler,
             in_object_key: false,
+            painter: Painter::new(AnsiHighlighter::default()),
+            color_depth: None,
+            rainbow: Vec::new(),
+            depth: 0,
+            paths: PathTracker::default(),
+            key_pending: false,
+            original_whitespace: false,
+            sort_keys: false,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Write HTML, instead of ANSI escape codes
+    ///
+    /// Tokens get wrapped in `<span>` elements, styled by CSS classes or inline styles, and their
+    /// content gets HTML-escaped. The output is always styled, regardless of the [`ColorMode`],
+    /// and colors are not downgraded. Use [`Styler::to_css`] for a stylesheet matching the
+    /// classes.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, HtmlStyle};
+    /// # use serde_json::json;
+    /// let html = ColoredFormatter::new(CompactFormatter {})
+    ///     .with_html(HtmlStyle::Classes)
+    ///     .to_colored_json(&json!({"a": "<b>"}), ColorMode::Off)?;
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<span class=\"json-object-bracket\">{</span>\
+    ///      <span class=\"json-key\">&quot;a&quot;</span>\
+    ///      <span class=\"json-colon\">:</span>\
+    ///      <span class=\"json-string\">&quot;&lt;b&gt;&quot;</span>\
+    ///      <span class=\"json-object-bracket\">}</span>"
+    /// );
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn with_html(self, style: HtmlStyle) -> ColoredFormatter<F, HtmlHighlighter> {
+        self.with_highlighter(HtmlHighlighter::new(style))
+    }
+}
+
+impl<F, H> ColoredFormatter<F, H>
+where
+    F: Formatter,
+    H: Highlighter,
+{
+    /// Write the tokens with a different highlighter, instead of ANSI escape codes
+    ///
+    /// See [`Highlighter`] for an example.
+    pub fn with_highlighter<H2>(self, highlighter: H2) -> ColoredFormatter<F, H2>
+    where
+        H2: Highlighter,
+    {
+        ColoredFormatter {
+            formatter: self.formatter,
+            styler: self.styler,
+            in_object_key: self.in_object_key,
+            painter: self.painter.with_highlighter(highlighter),
+            color_depth: self.color_depth,
+            rainbow: self.rainbow,
+            depth: self.depth,
+            paths: self.paths,
+            key_pending: self.key_pending,
+            original_whitespace: self.original_whitespace,
+            sort_keys: self.sort_keys,
+            limits: self.limits,
+        }
+    }
+
+    /// Set the color depth to use, instead of detecting it from the environment
+    ///
+    /// When writing colored output, all styles get downgraded to the nearest color which can be
+    /// displayed using this depth. Whether color is used at all is still decided by the
+    /// [`ColorMode`], so this works with auto-detection as well.
+    pub fn with_color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = Some(color_depth);
+        self
+    }
+
+    /// Color brackets depending on their nesting depth, cycling through the styles of the palette
+    ///
+    /// Matching opening and closing brackets get the same style. This overrides the style of
+    /// object and array brackets of the styler. An empty palette disables rainbow brackets.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColoredFormatter, PrettyFormatter, Styler};
+    /// let f = ColoredFormatter::new(PrettyFormatter::new())
+    ///     .with_rainbow_brackets(Styler::rainbow_palette());
+    /// ```
+    pub fn with_rainbow_brackets<I>(mut self, palette: I) -> Self
+    where
+        I: IntoIterator<Item = Style>,
+    {
+        self.rainbow = palette.into_iter().collect();
+        self
+    }
+
+    /// Override the styles of keys and values at paths matching the rule
+    ///
+    /// See [`PathRule`] for how paths are matched. Rules added later take precedence.
+    pub fn with_path_rule(mut self, rule: PathRule) -> Self {
+        self.paths.add_rule(rule);
+        self
+    }
+
+    /// Sort the keys of all objects, instead of keeping their order
+    ///
+    /// Keys are compared by their unescaped text, duplicate keys keep their order. So the output
+    /// is the same, no matter which order the serializer or the JSON text uses, e.g. with the
+    /// `preserve_order` feature of `serde_json` enabled.
+    ///
+    /// The entries of an object are buffered until the object ends, everything else is written
+    /// as it comes. So the memory needed is about the size of the largest object, not of the
+    /// whole document.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// let s = ColoredFormatter::new(CompactFormatter {})
+    ///     .with_sort_keys(true)
+    ///     .to_colored_str(r#"{"b": {"d": 1, "c": 2}, "a": 3}"#, ColorMode::Off)?;
+    /// assert_eq!(s, r#"{"a":3,"b":{"c":2,"d":1}}"#);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn with_sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Truncate string values longer than `max_length` characters
+    ///
+    /// The rest of the string is replaced by a marker with its size, like `…(1.9 MB elided)`, in
+    /// the elided style of the styler. Escape sequences count as one character, keys are never
+    /// truncated. The output is meant for reading, and not valid JSON anymore.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// # use serde_json::json;
+    /// let s = ColoredFormatter::new(CompactFormatter {})
+    ///     .with_max_string_length(4)
+    ///     .to_colored_json(&json!({"data": "a".repeat(2_000_000)}), ColorMode::Off)?;
+    /// assert_eq!(s, r#"{"data":"aaaa…(2.0 MB elided)"}"#);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn with_max_string_length(mut self, max_length: usize) -> Self {
+        self.limits.max_string_length = Some(max_length);
+        self
+    }
+
+    /// Only write the first `max_items` entries of arrays and objects
+    ///
+    /// The remaining entries are replaced by a marker, like `… 49,990 more items`, in the elided
+    /// style of the styler.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// # use serde_json::json;
+    /// let s = ColoredFormatter::new(CompactFormatter {})
+    ///     .with_max_items(2)
+    ///     .to_colored_json(&json!([1, 2, 3, 4]), ColorMode::Off)?;
+    /// assert_eq!(s, "[1,2,… 2 more items]");
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.limits.max_items = Some(max_items);
+        self
+    }
+
+    /// Only write `max_depth` levels of nested arrays and objects
+    ///
+    /// Deeper arrays and objects, which are not empty, are replaced by `[…]` and `{…}`, in the
+    /// elided style of the styler.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// # use serde_json::json;
+    /// let s = ColoredFormatter::new(CompactFormatter {})
+    ///     .with_max_depth(1)
+    ///     .to_colored_json(&json!({"a": {"b": 1}, "c": []}), ColorMode::Off)?;
+    /// assert_eq!(s, r#"{"a":{…},"c":[]}"#);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.limits.max_depth = Some(max_depth);
+        self
+    }
+
+    /// The rainbow style of an opening bracket, entering the next level
+    fn open_bracket_style(&mut self) -> Option<Style> {
+        let style = self.rainbow_style();
+        self.depth += 1;
+        style
+    }
+
+    /// The rainbow style of a closing bracket, leaving the current level
+    fn close_bracket_style(&mut self) -> Option<Style> {
+        self.depth = self.depth.saturating_sub(1);
+        self.rainbow_style()
+    }
+
+    fn rainbow_style(&self) -> Option<Style> {
+        match self.rainbow.len() {
+            0 => None,
+            len => Some(self.rainbow[self.depth % len]),
+        }
+    }
+
+    /// The style of the current string, which is either a key or a value
+    fn string_style(&self) -> (Role, Style) {
+        if self.in_object_key {
+            (Role::Key, self.styler.key)
+        } else {
+            (Role::String, self.styler.string_value)
+        }
+    }
+
+    /// The style of an escape sequence in the current string
+    fn escape_style(&self) -> (Role, Style) {
+        match self.styler.escape {
+            Some(style) => (Role::Escape, style),
+            None => self.string_style(),
         }
     }
 
@@ -244,7 +716,7 @@ where
     #[allow(clippy::wrong_self_convention)]
     pub fn to_colored_json<T>(self, value: &T, mode: ColorMode) -> serde_json::Result<String>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let mut writer: Vec<u8> = Vec::with_capacity(128);
 
@@ -261,169 +733,429 @@ where
     ) -> Result<(), serde_json::Error>
     where
         W: io::Write,
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        if mode.use_color() {
-            let mut serializer = serde_json::Serializer::with_formatter(writer, self);
+        if self.sort_keys || self.limits.is_active() {
+            let shaper = Shaper::new(self.for_mode(mode));
+            let mut serializer = serde_json::Serializer::with_formatter(writer, shaper);
+            return value.serialize(&mut serializer);
+        }
+
+        if self.use_color(mode) {
+            let mut serializer = serde_json::Serializer::with_formatter(writer, self.downgraded());
             value.serialize(&mut serializer)
         } else {
             let mut serializer = serde_json::Serializer::with_formatter(writer, self.formatter);
             value.serialize(&mut serializer)
         }
     }
+
+    /// Keep the whitespace of JSON text, instead of formatting it with the wrapped formatter
+    ///
+    /// This only applies to colorizing text, using [`ColoredFormatter::write_colored_str`], and
+    /// not when sorting keys or truncating.
+    pub fn with_original_whitespace(mut self, original_whitespace: bool) -> Self {
+        self.original_whitespace = original_whitespace;
+        self
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_colored_str(self, json: &str, mode: ColorMode) -> serde_json::Result<String> {
+        let mut writer: Vec<u8> = Vec::with_capacity(json.len() * 2);
+
+        self.write_colored_str(json, &mut writer, mode)?;
+
+        Ok(String::from_utf8_lossy(&writer).to_string())
+    }
+
+    /// Colorize JSON text, without parsing it into a [`serde_json::Value`]
+    ///
+    /// Unlike serializing a parsed value, this keeps the order of keys, duplicate keys and the
+    /// spelling of numbers, like `1.0e3`. The text gets validated first, so nothing is written
+    /// for invalid JSON.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// let s = ColoredFormatter::new(CompactFormatter {})
+    ///     .to_colored_str(r#"{ "b": 1.0e3, "a": null }"#, ColorMode::Off)?;
+    /// assert_eq!(s, r#"{"b":1.0e3,"a":null}"#);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn write_colored_str<W>(
+        self,
+        json: &str,
+        writer: &mut W,
+        mode: ColorMode,
+    ) -> serde_json::Result<()>
+    where
+        W: io::Write,
+    {
+        serde_json::from_str::<IgnoredAny>(json)?;
+        self.write_valid_str(json, writer, mode)
+            .map_err(serde_json::Error::io)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_colored_str_lenient(
+        self,
+        json: &str,
+        mode: ColorMode,
+    ) -> (String, Option<SyntaxError>) {
+        let mut writer: Vec<u8> = Vec::with_capacity(json.len() * 2);
+
+        // writing to a `Vec` doesn't fail
+        let error = self
+            .write_colored_str_lenient(json, &mut writer, mode)
+            .unwrap_or_default();
+
+        (String::from_utf8_lossy(&writer).to_string(), error)
+    }
+
+    /// Colorize JSON text, which may be invalid or truncated
+    ///
+    /// Valid JSON is written like by [`ColoredFormatter::write_colored_str`]. Otherwise, the text
+    /// is colored as far as possible, keeping its whitespace, and the invalid input is marked with
+    /// the error style of the styler. If the input ends too early, its last token is marked. The
+    /// returned error tells where the document broke.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter};
+    /// let (s, error) = ColoredFormatter::new(CompactFormatter {})
+    ///     .to_colored_str_lenient("{\"a\": [1, 2", ColorMode::Off);
+    /// assert_eq!(s, "{\"a\": [1, 2");
+    ///
+    /// let error = error.expect("truncated input");
+    /// assert_eq!((error.offset(), error.line(), error.column()), (11, 1, 11));
+    /// ```
+    pub fn write_colored_str_lenient<W>(
+        self,
+        json: &str,
+        writer: &mut W,
+        mode: ColorMode,
+    ) -> io::Result<Option<SyntaxError>>
+    where
+        W: io::Write,
+    {
+        match serde_json::from_str::<IgnoredAny>(json) {
+            Ok(_) => self.write_valid_str(json, writer, mode).map(|_| None),
+            Err(error) => {
+                let error = SyntaxError::new(json, error);
+                let offset = match json.get(error.offset()..) {
+                    Some(rest) if !rest.trim().is_empty() => Some(error.offset()),
+                    // there is no token at the end of the input, where the document broke
+                    _ => lexer::last_token_offset(json),
+                };
+                self.for_mode(mode).write_lexed(writer, json, offset)?;
+                Ok(Some(error))
+            }
+        }
+    }
+
+    fn write_valid_str<W>(self, json: &str, writer: &mut W, mode: ColorMode) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let mut formatter = self.for_mode(mode);
+        if formatter.original_whitespace && !formatter.sort_keys && !formatter.limits.is_active() {
+            formatter.write_raw_fragment(writer, json)
+        } else {
+            Shaper::new(formatter).write_tokens(writer, json)
+        }
+    }
+
+    /// Prepare for writing text, with or without color
+    fn for_mode(mut self, mode: ColorMode) -> Self {
+        if self.use_color(mode) {
+            self.downgraded()
+        } else {
+            self.painter.plain = true;
+            self
+        }
+    }
+
+    /// Only output for a terminal depends on the color mode
+    fn use_color(&self, mode: ColorMode) -> bool {
+        !self.painter.highlighter.is_terminal() || mode.use_color()
+    }
+
+    /// Convert all styles to colors the terminal can display
+    fn downgraded(mut self) -> Self {
+        if !self.painter.highlighter.is_terminal() {
+            return self;
+        }
+        let depth = self.color_depth.unwrap_or_else(ColorDepth::detect);
+        self.styler = self.styler.downgrade(depth);
+        for style in &mut self.rainbow {
+            *style = depth.downgrade_style(*style);
+        }
+        self.paths.downgrade(depth);
+        self
+    }
 }
 
-fn colored<W: ?Sized, H>(writer: &mut W, style: Style, mut handler: H) -> io::Result<()>
+#[allow(clippy::multiple_bound_locations)]
+fn colored<W: ?Sized, H, C>(
+    painter: &mut Painter<H>,
+    writer: &mut W,
+    role: Role,
+    style: Style,
+    handler: C,
+) -> io::Result<()>
 where
     W: io::Write,
-    H: FnMut(&mut Vec<u8>) -> io::Result<()>,
+    H: Highlighter,
+    C: FnOnce(&mut StyledWriter<'_, W, H>) -> io::Result<()>,
 {
-    let mut w: Vec<u8> = Vec::with_capacity(128);
+    let mut w = painter.writer(writer, role, style);
     handler(&mut w)?;
+    w.finish()
+}
 
-    if !w.is_empty() {
-        let s = String::from_utf8_lossy(&w);
-        writer.write_all(style.paint(s).to_string().as_bytes())?;
-    }
-
-    Ok(())
+/// Like [`colored`], with the style of the rainbow palette overriding the style of the role
+///
+/// The style of a path rule still takes precedence.
+#[allow(clippy::multiple_bound_locations)]
+fn rainbow_colored<W: ?Sized, H, C>(
+    painter: &mut Painter<H>,
+    writer: &mut W,
+    role: Role,
+    style: Style,
+    rainbow: Option<Style>,
+    handler: C,
+) -> io::Result<()>
+where
+    W: io::Write,
+    H: Highlighter,
+    C: FnOnce(&mut StyledWriter<'_, W, H>) -> io::Result<()>,
+{
+    let style_override = painter.style_override;
+    painter.style_override = style_override.or(rainbow);
+    let result = colored(painter, writer, role, style, handler);
+    painter.style_override = style_override;
+    result
 }
 
-impl<F> Formatter for ColoredFormatter<F>
+#[allow(clippy::multiple_bound_locations)]
+impl<F, H> Formatter for ColoredFormatter<F, H>
 where
     F: Formatter,
+    H: Highlighter,
 {
     fn write_null<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.nil_value, |w| {
-            self.formatter.write_null(w)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Null,
+            self.styler.nil_value,
+            |w| self.formatter.write_null(w),
+        )
     }
 
     fn write_bool<W: ?Sized>(&mut self, writer: &mut W, value: bool) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.bool_value, |w| {
-            self.formatter.write_bool(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::bool(value),
+            self.styler.bool_style(value),
+            |w| self.formatter.write_bool(w, value),
+        )
     }
 
     fn write_i8<W: ?Sized>(&mut self, writer: &mut W, value: i8) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_i8(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_i8(w, value),
+        )
     }
 
     fn write_i16<W: ?Sized>(&mut self, writer: &mut W, value: i16) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_i16(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_i16(w, value),
+        )
     }
 
     fn write_i32<W: ?Sized>(&mut self, writer: &mut W, value: i32) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_i32(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_i32(w, value),
+        )
     }
 
     fn write_i64<W: ?Sized>(&mut self, writer: &mut W, value: i64) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_i64(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_i64(w, value),
+        )
+    }
+
+    fn write_i128<W: ?Sized>(&mut self, writer: &mut W, value: i128) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_i128(w, value),
+        )
     }
 
     fn write_u8<W: ?Sized>(&mut self, writer: &mut W, value: u8) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_u8(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_u8(w, value),
+        )
     }
 
     fn write_u16<W: ?Sized>(&mut self, writer: &mut W, value: u16) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_u16(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_u16(w, value),
+        )
     }
 
     fn write_u32<W: ?Sized>(&mut self, writer: &mut W, value: u32) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_u32(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_u32(w, value),
+        )
     }
 
     fn write_u64<W: ?Sized>(&mut self, writer: &mut W, value: u64) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
-            self.formatter.write_u64(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_u64(w, value),
+        )
+    }
+
+    fn write_u128<W: ?Sized>(&mut self, writer: &mut W, value: u128) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Integer,
+            self.styler.integer_value,
+            |w| self.formatter.write_u128(w, value),
+        )
     }
 
     fn write_f32<W: ?Sized>(&mut self, writer: &mut W, value: f32) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.float_value, |w| {
-            self.formatter.write_f32(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Float,
+            self.styler.float_value,
+            |w| self.formatter.write_f32(w, value),
+        )
     }
 
     fn write_f64<W: ?Sized>(&mut self, writer: &mut W, value: f64) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.float_value, |w| {
-            self.formatter.write_f64(w, value)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Float,
+            self.styler.float_value,
+            |w| self.formatter.write_f64(w, value),
+        )
     }
 
     fn write_number_str<W: ?Sized>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.integer_value, |w| {
+        let (role, style) = if lexer::is_float(value) {
+            (Role::Float, self.styler.float_value)
+        } else {
+            (Role::Integer, self.styler.integer_value)
+        };
+        colored(&mut self.painter, writer, role, style, |w| {
             self.formatter.write_number_str(w, value)
         })
-        //        self.formatter.write_number_str(writer, value)
     }
 
     fn begin_string<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
+        if std::mem::take(&mut self.key_pending) {
+            // the style of the key depends on the key itself, so it gets written once complete
+            self.painter.start_capture();
+            return Ok(());
+        }
+
+        let (role, style) = self.string_style();
         if self.styler.string_include_quotation {
-            let style = if self.in_object_key {
-                self.styler.key
-            } else {
-                self.styler.string_value
-            };
-            colored(writer, style, |w| self.formatter.begin_string(w))
+            self.painter.begin_string(writer, role, style)?;
+            colored(&mut self.painter, writer, role, style, |w| {
+                self.formatter.begin_string(w)
+            })
         } else {
-            self.formatter.begin_string(writer)
+            self.formatter.begin_string(writer)?;
+            self.painter.begin_string(writer, role, style)
         }
     }
 
@@ -431,14 +1163,19 @@ where
     where
         W: io::Write,
     {
+        if let Some(key) = self.painter.finish_capture() {
+            return self.write_key(writer, &String::from_utf8_lossy(&key));
+        }
+
         if self.styler.string_include_quotation {
-            let style = if self.in_object_key {
-                self.styler.key
-            } else {
-                self.styler.string_value
-            };
-            colored(writer, style, |w| self.formatter.end_string(w))
+            let (role, style) = self.string_style();
+            colored(&mut self.painter, writer, role, style, |w| {
+                self.formatter.end_string(w)
+            })?;
+            self.painter.end_string(writer, role, style)
         } else {
+            let (role, style) = self.string_style();
+            self.painter.end_string(writer, role, style)?;
             self.formatter.end_string(writer)
         }
     }
@@ -447,64 +1184,121 @@ where
     where
         W: io::Write,
     {
-        let style = if self.in_object_key {
-            self.styler.key
-        } else {
-            self.styler.string_value
-        };
-        colored(writer, style, |w| {
+        let (role, style) = self.string_style();
+        colored(&mut self.painter, writer, role, style, |w| {
             self.formatter.write_string_fragment(w, fragment)
         })
     }
 
-    fn begin_array<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    fn write_char_escape<W: ?Sized>(
+        &mut self,
+        writer: &mut W,
+        char_escape: CharEscape,
+    ) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.array_brackets, |w| {
-            self.formatter.begin_array(w)
+        let (role, style) = self.escape_style();
+        colored(&mut self.painter, writer, role, style, |w| {
+            self.formatter.write_char_escape(w, char_escape)
         })
     }
 
+    fn begin_array<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let rainbow = self.open_bracket_style();
+        rainbow_colored(
+            &mut self.painter,
+            writer,
+            Role::ArrayBracket,
+            self.styler.array_brackets,
+            rainbow,
+            |w| self.formatter.begin_array(w),
+        )?;
+        if self.paths.is_active() {
+            self.paths.begin_array();
+        }
+        Ok(())
+    }
+
     fn end_array<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.array_brackets, |w| {
-            self.formatter.end_array(w)
-        })
+        if self.paths.is_active() {
+            self.paths.end_container();
+        }
+        let rainbow = self.close_bracket_style();
+        rainbow_colored(
+            &mut self.painter,
+            writer,
+            Role::ArrayBracket,
+            self.styler.array_brackets,
+            rainbow,
+            |w| self.formatter.end_array(w),
+        )
     }
 
     fn begin_array_value<W: ?Sized>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
     where
         W: io::Write,
     {
-        self.formatter.begin_array_value(writer, first)
+        self.formatter.begin_array_value(writer, first)?;
+        if self.paths.is_active() {
+            self.paths.next_index(first);
+            self.painter.style_override = self.paths.begin_value();
+        }
+        Ok(())
     }
 
     fn end_array_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        self.formatter.end_array_value(writer)
+        self.formatter.end_array_value(writer)?;
+        if self.paths.is_active() {
+            self.painter.style_override = self.paths.end_value();
+        }
+        Ok(())
     }
 
     fn begin_object<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.object_brackets, |w| {
-            self.formatter.begin_object(w)
-        })
+        let rainbow = self.open_bracket_style();
+        rainbow_colored(
+            &mut self.painter,
+            writer,
+            Role::ObjectBracket,
+            self.styler.object_brackets,
+            rainbow,
+            |w| self.formatter.begin_object(w),
+        )?;
+        if self.paths.is_active() {
+            self.paths.begin_object();
+        }
+        Ok(())
     }
 
     fn end_object<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: io::Write,
     {
-        colored(writer, self.styler.object_brackets, |w| {
-            self.formatter.end_object(w)
-        })
+        if self.paths.is_active() {
+            self.paths.end_container();
+        }
+        let rainbow = self.close_bracket_style();
+        rainbow_colored(
+            &mut self.painter,
+            writer,
+            Role::ObjectBracket,
+            self.styler.object_brackets,
+            rainbow,
+            |w| self.formatter.end_object(w),
+        )
     }
 
     fn begin_object_key<W: ?Sized>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
@@ -512,6 +1306,7 @@ where
         W: io::Write,
     {
         self.in_object_key = true;
+        self.key_pending = self.paths.is_active();
         self.formatter.begin_object_key(writer, first)
     }
 
@@ -520,9 +1315,13 @@ where
         W: io::Write,
     {
         self.in_object_key = false;
-        colored(writer, self.styler.object_colon, |w| {
-            self.formatter.end_object_key(w)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Colon,
+            self.styler.object_colon,
+            |w| self.formatter.end_object_key(w),
+        )
     }
 
     fn begin_object_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
@@ -530,9 +1329,17 @@ where
         W: io::Write,
     {
         self.in_object_key = false;
-        colored(writer, self.styler.object_colon, |w| {
-            self.formatter.begin_object_value(w)
-        })
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Colon,
+            self.styler.object_colon,
+            |w| self.formatter.begin_object_value(w),
+        )?;
+        if self.paths.is_active() {
+            self.painter.style_override = self.paths.begin_value();
+        }
+        Ok(())
     }
 
     fn end_object_value<W: ?Sized>(&mut self, writer: &mut W) -> io::Result<()>
@@ -541,6 +1348,9 @@ where
     {
         self.in_object_key = false;
         self.formatter.end_object_value(writer)?;
+        if self.paths.is_active() {
+            self.painter.style_override = self.paths.end_value();
+        }
         Ok(())
     }
 
@@ -548,7 +1358,182 @@ where
     where
         W: io::Write,
     {
-        self.formatter.write_raw_fragment(writer, fragment)
+        self.write_lexed(writer, fragment, None)
+    }
+}
+
+impl<F, H> ColoredFormatter<F, H>
+where
+    F: Formatter,
+    H: Highlighter,
+{
+    /// Write JSON text as it is, coloring its tokens
+    ///
+    /// Tokens which are not valid JSON, and the token at `error_offset`, get the error style.
+    fn write_lexed<W>(
+        &mut self,
+        writer: &mut W,
+        text: &str,
+        error_offset: Option<usize>,
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let in_object_key = self.in_object_key;
+        let mut offset = 0;
+
+        for (kind, token) in Lexer::new(text) {
+            let start = offset;
+            offset += token.len();
+
+            let invalid = kind == TokenKind::Error
+                || (kind != TokenKind::Whitespace
+                    && error_offset.is_some_and(|error| (start..offset).contains(&error)));
+
+            let (role, style, rainbow) = match kind {
+                _ if invalid => (Role::Error, self.styler.error_style(), None),
+                TokenKind::Key | TokenKind::String => {
+                    self.in_object_key = kind == TokenKind::Key;
+                    self.write_string_token(writer, token)?;
+                    continue;
+                }
+                TokenKind::Whitespace | TokenKind::Comma | TokenKind::Error => {
+                    writer.write_all(token.as_bytes())?;
+                    continue;
+                }
+                TokenKind::BeginObject => (
+                    Role::ObjectBracket,
+                    self.styler.object_brackets,
+                    self.open_bracket_style(),
+                ),
+                TokenKind::EndObject => (
+                    Role::ObjectBracket,
+                    self.styler.object_brackets,
+                    self.close_bracket_style(),
+                ),
+                TokenKind::BeginArray => (
+                    Role::ArrayBracket,
+                    self.styler.array_brackets,
+                    self.open_bracket_style(),
+                ),
+                TokenKind::EndArray => (
+                    Role::ArrayBracket,
+                    self.styler.array_brackets,
+                    self.close_bracket_style(),
+                ),
+                TokenKind::Colon => (Role::Colon, self.styler.object_colon, None),
+                TokenKind::Integer => (Role::Integer, self.styler.integer_value, None),
+                TokenKind::Float => (Role::Float, self.styler.float_value, None),
+                TokenKind::Bool => {
+                    let value = token == "true";
+                    (Role::bool(value), self.styler.bool_style(value), None)
+                }
+                TokenKind::Null => (Role::Null, self.styler.nil_value, None),
+            };
+            rainbow_colored(&mut self.painter, writer, role, style, rainbow, |w| {
+                w.write_all(token.as_bytes())
+            })?;
+        }
+
+        self.in_object_key = in_object_key;
+        Ok(())
+    }
+
+    /// Write a string token of raw JSON, including the quotes, as if it was serialized
+    fn write_string_token<W>(&mut self, writer: &mut W, token: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.write_string_content(writer, &token[1..token.len() - 1])
+    }
+
+    /// Write the marker for the entries of an array or object beyond the maximum number of items
+    fn write_more_items<W>(
+        &mut self,
+        writer: &mut W,
+        object: bool,
+        first: bool,
+        count: usize,
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        // the marker takes the place of an entry, but has no path
+        if object {
+            self.formatter.begin_object_key(writer, first)?;
+        } else {
+            self.formatter.begin_array_value(writer, first)?;
+        }
+        self.write_elided(writer, &truncate::more_items(count))?;
+        if object {
+            self.formatter.end_object_value(writer)
+        } else {
+            self.formatter.end_array_value(writer)
+        }
+    }
+
+    /// Write a marker for elided content
+    fn write_elided<W>(&mut self, writer: &mut W, marker: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        colored(
+            &mut self.painter,
+            writer,
+            Role::Elided,
+            self.styler.elided_style(),
+            |w| w.write_all(marker.as_bytes()),
+        )
+    }
+
+    /// Write a captured object key, applying the style of matching path rules
+    fn write_key<W>(&mut self, writer: &mut W, key: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.paths.set_key(key);
+        let style_override = self.painter.style_override;
+        if let Some(style) = self.paths.key_style() {
+            self.painter.style_override = Some(style);
+        }
+        let result = self.write_string_content(writer, key);
+        self.painter.style_override = style_override;
+        result
+    }
+
+    /// Write the escaped content of a string, adding the quotes
+    fn write_string_content<W>(&mut self, writer: &mut W, content: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_string(writer)?;
+        self.write_string_parts(writer, content)?;
+        self.end_string(writer)
+    }
+
+    /// Write the fragments and escape sequences of the escaped content of a string
+    fn write_string_parts<W>(&mut self, writer: &mut W, content: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        for part in lexer::string_parts(content) {
+            match part {
+                StringPart::Fragment(fragment) => self.write_string_fragment(writer, fragment)?,
+                StringPart::Escape(escape) => self.write_escape(writer, escape)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write an escape sequence of JSON text
+    fn write_escape<W>(&mut self, writer: &mut W, escape: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (role, style) = self.escape_style();
+        colored(&mut self.painter, writer, role, style, |w| {
+            w.write_all(escape.as_bytes())
+        })
     }
 }
 
@@ -579,63 +1564,97 @@ pub trait ToColoredJson {
     ) -> serde_json::Result<()>
     where
         W: io::Write;
+
+    /// Colorize JSON text as a pretty-color-printed String of JSON, formatted with the options.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text is not valid JSON.
+    fn to_colored_json_with_options(
+        &self,
+        mode: ColorMode,
+        options: &FormatOptions,
+    ) -> serde_json::Result<String>
+    where
+        Self: AsRef<str>,
+    {
+        let json = self.as_ref();
+        let mut writer: Vec<u8> = Vec::with_capacity(json.len() * 2);
+
+        options.write_str(json, &mut writer, mode)?;
+
+        Ok(String::from_utf8_lossy(&writer).to_string())
+    }
+
+    /// Colorize JSON text as pretty-color-printed JSON into the IO stream, formatted with the
+    /// options.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text is not valid JSON, or writing fails.
+    fn write_colored_json_with_options<W>(
+        &self,
+        writer: &mut W,
+        mode: ColorMode,
+        options: &FormatOptions,
+    ) -> serde_json::Result<()>
+    where
+        W: io::Write,
+        Self: AsRef<str>,
+    {
+        options.write_str(self.as_ref(), writer, mode)
+    }
 }
 
 impl<S> ToColoredJson for S
 where
     S: ?Sized + AsRef<str>,
 {
-    /// Serialize the given data structure as a pretty-color-printed String of JSON.
+    /// Colorize JSON text as a pretty-color-printed String of JSON.
+    ///
+    /// The order of keys and the spelling of numbers is kept, see
+    /// [`ColoredFormatter::write_colored_str`].
     ///
     /// # Errors
     ///
-    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// Fails if the text is not valid JSON.
     fn to_colored_json_auto(&self) -> serde_json::Result<String> {
-        let v: Value = serde_json::from_str(self.as_ref())?;
-        to_colored_json(&v, ColorMode::Auto(Output::StdOut))
+        self.to_colored_json(ColorMode::Auto(Output::StdOut))
     }
 
-    /// Serialize the given data structure as a pretty-color-printed String of JSON.
+    /// Colorize JSON text as a pretty-color-printed String of JSON.
     ///
     /// # Errors
     ///
-    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// Fails if the text is not valid JSON.
     fn to_colored_json(&self, mode: ColorMode) -> serde_json::Result<String> {
-        let v: Value = serde_json::from_str(self.as_ref())?;
-        to_colored_json(&v, mode)
+        ColoredFormatter::new(PrettyFormatter::new()).to_colored_str(self.as_ref(), mode)
     }
 
-    /// Serialize the given data structure as a pretty-color-printed String of JSON.
+    /// Colorize JSON text as a pretty-color-printed String of JSON.
     ///
     /// # Errors
     ///
-    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// Fails if the text is not valid JSON.
     fn to_colored_json_with_styler(
         &self,
         mode: ColorMode,
         styler: Styler,
     ) -> serde_json::Result<String> {
         let f = ColoredFormatter::with_styler(PrettyFormatter::new(), styler);
-        let v: Value = serde_json::from_str(self.as_ref())?;
-        f.to_colored_json(&v, mode)
+        f.to_colored_str(self.as_ref(), mode)
     }
 
-    /// Serialize the given data structure as pretty-color-printed JSON into the IO
-    /// stream.
+    /// Colorize JSON text as pretty-color-printed JSON into the IO stream.
     ///
     /// # Errors
     ///
-    /// Serialization can fail if `T`'s implementation of `Serialize` decides to
-    /// fail, or if `T` contains a map with non-string keys.
+    /// Fails if the text is not valid JSON, or writing fails.
     fn write_colored_json<W>(&self, writer: &mut W) -> serde_json::Result<()>
     where
         W: io::Write,
     {
-        let value: Value = serde_json::from_str(self.as_ref())?;
-        write_colored_json_with_mode(&value, writer, ColorMode::Auto(Output::StdOut))
+        self.write_colored_json_with_mode(writer, ColorMode::Auto(Output::StdOut))
     }
 
     fn write_colored_json_with_mode<W>(
@@ -646,8 +1665,7 @@ where
     where
         W: io::Write,
     {
-        let value: Value = serde_json::from_str(self.as_ref())?;
-        write_colored_json_with_mode(&value, writer, mode)
+        ColoredFormatter::new(PrettyFormatter::new()).write_colored_str(self.as_ref(), writer, mode)
     }
 
     fn write_colored_json_with_styler<W>(
@@ -659,9 +1677,8 @@ where
     where
         W: io::Write,
     {
-        let value: Value = serde_json::from_str(self.as_ref())?;
         let f = ColoredFormatter::with_styler(PrettyFormatter::new(), styler);
-        f.write_colored_json(&value, writer, mode)
+        f.write_colored_str(self.as_ref(), writer, mode)
     }
 }
 
@@ -713,27 +1730,104 @@ where
     W: io::Write,
     T: Serialize,
 {
-    if mode.use_color() {
-        let formatter = ColoredFormatter::new(PrettyFormatter::new());
-        let mut serializer = serde_json::Serializer::with_formatter(writer, formatter);
-        value.serialize(&mut serializer)
-    } else {
-        let formatter = PrettyFormatter::new();
-        let mut serializer = serde_json::Serializer::with_formatter(writer, formatter);
-        value.serialize(&mut serializer)
-    }
+    ColoredFormatter::new(PrettyFormatter::new()).write_colored_json(value, writer, mode)
+}
+
+/// Serialize the given data structure as a pretty-color-printed String of JSON, formatted with
+/// the options.
+///
+/// See [`FormatOptions`] for the indentation and the maximum line width.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn to_colored_json_with_options<T>(
+    value: &T,
+    mode: ColorMode,
+    options: &FormatOptions,
+) -> serde_json::Result<String>
+where
+    T: Serialize,
+{
+    let mut writer: Vec<u8> = Vec::with_capacity(128);
+
+    write_colored_json_with_options(value, &mut writer, mode, options)?;
+
+    Ok(String::from_utf8_lossy(&writer).to_string())
+}
+
+/// Serialize the given data structure as pretty-color-printed JSON into the IO
+/// stream, formatted with the options.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn write_colored_json_with_options<W, T>(
+    value: &T,
+    writer: &mut W,
+    mode: ColorMode,
+    options: &FormatOptions,
+) -> serde_json::Result<()>
+where
+    W: io::Write,
+    T: Serialize,
+{
+    options.write_value(value, writer, mode)
+}
+
+/// Serialize the given data structure with the formatter, passing its tokens to the visitor
+///
+/// This splits the output into the same tokens the [`ColoredFormatter`] colors, for rendering
+/// them in other ways. For JSON text, use a [`Lexer`] directly.
+///
+/// ```rust
+/// # use colored_json::{visit_tokens, PrettyFormatter, TokenKind};
+/// # use serde_json::json;
+/// let mut keys = Vec::new();
+/// visit_tokens(&json!({"a": [1, 2]}), PrettyFormatter::new(), |kind, text| {
+///     if kind == TokenKind::Key {
+///         keys.push(text.to_string());
+///     }
+/// })?;
+/// assert_eq!(keys, ["\"a\""]);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn visit_tokens<T, F, V>(value: &T, formatter: F, visitor: V) -> serde_json::Result<()>
+where
+    T: ?Sized + Serialize,
+    F: Formatter,
+    V: FnMut(TokenKind, &str),
+{
+    // the formatter passes the tokens on while serializing, the writer gets commas and whitespace
+    let sink = RefCell::new(TokenSink::new(visitor));
+    let f = ColoredFormatter::new(formatter).with_highlighter(TokenForwarder::new(&sink));
+    value.serialize(&mut serde_json::Serializer::with_formatter(
+        Separators(&sink),
+        f,
+    ))?;
+
+    sink.borrow_mut().flush();
+    Ok(())
 }
 
 /// ColorMode is a switch to enforce color mode, turn it off or auto-detect, if it should be used
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ColorMode {
     On,
     Off,
+    /// Auto-detect, see [`ColorMode::should_colorize_with`] for the rules
     Auto(Output),
 }
 
 /// Specify the output sink, which should be used for the auto detection
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Output {
     StdOut,
     StdErr,
@@ -760,16 +1854,126 @@ pub enum Output {
 /// });
 /// ```
 impl ColorMode {
-    fn is_tty(output: Output) -> bool {
-        match output {
-            Output::StdOut => io::stdout().is_terminal(),
-            Output::StdErr => io::stderr().is_terminal(),
+    /// indicates, if the `output` is a capable of displaying colors
+    ///
+    /// This evaluates the process environment, see [`ColorMode::should_colorize_with`].
+    pub fn should_colorize(output: Output) -> bool {
+        Self::should_colorize_with(output, &ProcessEnvironment)
+    }
+
+    /// indicates, if the `output` is a capable of displaying colors, using the provided environment
+    ///
+    /// The following rules are evaluated in order, the first one which matches wins:
+    ///
+    /// 1. `NO_COLOR` is set to a non-empty value: no color
+    /// 2. `CLICOLOR_FORCE` is set to a non-empty value other than `0`: color
+    /// 3. `CLICOLOR` is set to `0`: no color
+    /// 4. `TERM` is set to `dumb`: no color
+    /// 5. color, if the `output` is a terminal
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, Environment, Output};
+    /// struct Forced;
+    ///
+    /// impl Environment for Forced {
+    ///     fn var(&self, name: &str) -> Option<String> {
+    ///         (name == "CLICOLOR_FORCE").then(|| "1".to_string())
+    ///     }
+    ///
+    ///     fn is_terminal(&self, _output: Output) -> bool {
+    ///         false
+    ///     }
+    /// }
+    ///
+    /// assert!(ColorMode::should_colorize_with(Output::StdOut, &Forced));
+    /// ```
+    pub fn should_colorize_with<E>(output: Output, env: &E) -> bool
+    where
+        E: Environment + ?Sized,
+    {
+        Self::forced_with(env).unwrap_or_else(|| env.is_terminal(output))
+    }
+
+    /// The color mode for the output of tests, like failed assertions
+    ///
+    /// This evaluates the process environment, see [`ColorMode::for_tests_with`].
+    pub fn for_tests() -> Self {
+        Self::for_tests_with(&ProcessEnvironment)
+    }
+
+    /// The color mode for the output of tests, using the provided environment
+    ///
+    /// Returns `ColorMode::On` or `ColorMode::Off`. The rules of
+    /// [`ColorMode::should_colorize_with`] are evaluated first, then:
+    ///
+    /// 1. `CI` is set to a non-empty value other than `0` or `false`: no color
+    /// 2. the output is captured by the test harness: no color, unless `--nocapture` is passed or
+    ///    `RUST_TEST_NOCAPTURE` is set
+    /// 3. color, if `stderr` is a terminal
+    ///
+    /// ```rust
+    /// # use colored_json::{ColorMode, Environment, Output};
+    /// struct Ci;
+    ///
+    /// impl Environment for Ci {
+    ///     fn var(&self, name: &str) -> Option<String> {
+    ///         (name == "CI").then(|| "true".to_string())
+    ///     }
+    ///
+    ///     fn is_terminal(&self, _output: Output) -> bool {
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(ColorMode::for_tests_with(&Ci), ColorMode::Off);
+    /// ```
+    pub fn for_tests_with<E>(env: &E) -> Self
+    where
+        E: Environment + ?Sized,
+    {
+        let enabled = |name: &str| {
+            env.var(name)
+                .is_some_and(|value| !matches!(value.as_str(), "" | "0" | "false"))
+        };
+        let captured = || {
+            !enabled("RUST_TEST_NOCAPTURE")
+                && !env
+                    .args()
+                    .iter()
+                    .any(|arg| arg == "--nocapture" || arg == "--no-capture")
+        };
+
+        let use_color = Self::forced_with(env)
+            .unwrap_or_else(|| !enabled("CI") && !captured() && env.is_terminal(Output::StdErr));
+        if use_color {
+            ColorMode::On
+        } else {
+            ColorMode::Off
         }
     }
 
-    /// indicates, if the `output` is a capable of displaying colors
-    pub fn should_colorize(output: Output) -> bool {
-        Self::is_tty(output)
+    /// The decision of the environment variables, `None` if they don't decide
+    fn forced_with<E>(env: &E) -> Option<bool>
+    where
+        E: Environment + ?Sized,
+    {
+        let is_set = |name: &str| env.var(name).is_some_and(|value| !value.is_empty());
+
+        if is_set("NO_COLOR") {
+            return Some(false);
+        }
+        if is_set("CLICOLOR_FORCE") && env.var("CLICOLOR_FORCE").as_deref() != Some("0") {
+            return Some(true);
+        }
+        if env.var("CLICOLOR").as_deref() == Some("0") {
+            return Some(false);
+        }
+        if env.var("TERM").as_deref() == Some("dumb") {
+            return Some(false);
+        }
+        None
     }
 
     /// Returns ColorMode::On or ColorMode::Off
@@ -816,10 +2020,18 @@ impl ColorMode {
     /// assert_eq!(ColorMode::Off.use_color(), false);
     /// ```
     pub fn use_color(self) -> bool {
+        self.use_color_with(&ProcessEnvironment)
+    }
+
+    /// Indicates if color should be used, evaluating `ColorMode::Auto` with the provided environment
+    pub fn use_color_with<E>(self, env: &E) -> bool
+    where
+        E: Environment + ?Sized,
+    {
         match self {
             ColorMode::On => true,
             ColorMode::Off => false,
-            ColorMode::Auto(output) => Self::should_colorize(output),
+            ColorMode::Auto(output) => Self::should_colorize_with(output, env),
         }
     }
 }
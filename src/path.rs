@@ -0,0 +1,309 @@
+use crate::ColorDepth;
+use std::fmt::Write;
+use yansi::Style;
+
+/// A rule, overriding the style of object keys and values at matching paths
+///
+/// Paths are written as [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901), like
+/// `/metadata/name` or `/items/0`. A rule can override the style of the key, and the style of the
+/// whole value, including everything nested in it. When multiple rules match, the last one wins.
+///
+/// ```rust
+/// # use colored_json::{Color, ColoredFormatter, PathRule, PrettyFormatter, Style};
+/// let f = ColoredFormatter::new(PrettyFormatter::new())
+///     .with_path_rule(PathRule::key("error").style(Style::new(Color::Red).bold()))
+///     .with_path_rule(PathRule::pointer("/metadata/name").value_style(Style::new(Color::Cyan)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathRule {
+    matcher: Matcher,
+    key_style: Option<Style>,
+    value_style: Option<Style>,
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Key(String),
+    Pointer(Vec<Glob>),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Glob {
+    Segment(String),
+    /// `*`, any single segment
+    Any,
+    /// `**`, any number of segments
+    AnyDepth,
+}
+
+impl PathRule {
+    /// Match all object entries with this key, at any depth
+    pub fn key<S>(key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(Matcher::Key(key.into()))
+    }
+
+    /// Match a JSON Pointer, which may contain wildcards
+    ///
+    /// A segment of `*` matches any single key or array index, a segment of `**` matches any
+    /// number of segments. For example, `/items/*/id` matches the `id` of all items and
+    /// `/**/name` matches `name` at any depth.
+    pub fn pointer(pointer: &str) -> Self {
+        let globs = pointer
+            .split('/')
+            .skip(1)
+            .map(|segment| match segment {
+                "*" => Glob::Any,
+                "**" => Glob::AnyDepth,
+                segment => Glob::Segment(segment.replace("~1", "/").replace("~0", "~")),
+            })
+            .collect();
+        Self::new(Matcher::Pointer(globs))
+    }
+
+    /// Match the JSON Pointer of a path with a regular expression
+    ///
+    /// ```rust
+    /// # use colored_json::{Color, PathRule, Style};
+    /// let regex = regex::Regex::new(r"^/items/\d+/id$").expect("valid regex");
+    /// let rule = PathRule::regex(regex).value_style(Style::new(Color::Yellow));
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn regex(regex: regex::Regex) -> Self {
+        Self::new(Matcher::Regex(regex))
+    }
+
+    fn new(matcher: Matcher) -> Self {
+        PathRule {
+            matcher,
+            key_style: None,
+            value_style: None,
+        }
+    }
+
+    /// Override the style of the key and the value
+    pub fn style(self, style: Style) -> Self {
+        self.key_style(style).value_style(style)
+    }
+
+    /// Override the style of the key
+    pub fn key_style(mut self, style: Style) -> Self {
+        self.key_style = Some(style);
+        self
+    }
+
+    /// Override the style of the value, including everything nested in it
+    pub fn value_style(mut self, style: Style) -> Self {
+        self.value_style = Some(style);
+        self
+    }
+
+    #[cfg_attr(not(feature = "regex"), allow(unused_variables))]
+    fn is_match(&self, path: &[Segment], pointer: &mut Option<String>) -> bool {
+        match &self.matcher {
+            Matcher::Key(key) => matches!(path.last(), Some(Segment::Key(last)) if last == key),
+            Matcher::Pointer(globs) => glob_match(globs, path),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => {
+                regex.is_match(pointer.get_or_insert_with(|| to_pointer(path)))
+            }
+        }
+    }
+
+    fn downgrade(&mut self, depth: ColorDepth) {
+        self.key_style = self.key_style.map(|style| depth.downgrade_style(style));
+        self.value_style = self.value_style.map(|style| depth.downgrade_style(style));
+    }
+}
+
+fn glob_match(globs: &[Glob], path: &[Segment]) -> bool {
+    match (globs.split_first(), path.split_first()) {
+        (None, _) => path.is_empty(),
+        (Some((Glob::AnyDepth, rest)), _) => (0..=path.len()).any(|i| glob_match(rest, &path[i..])),
+        (Some((glob, rest)), Some((segment, tail))) => {
+            let matches = match (glob, segment) {
+                (Glob::Any, _) => true,
+                (Glob::Segment(expected), Segment::Key(key)) => expected == key,
+                (Glob::Segment(expected), Segment::Index(index)) => {
+                    expected.parse::<usize>().ok() == Some(*index)
+                }
+                (Glob::AnyDepth, _) => unreachable!(),
+            };
+            matches && glob_match(rest, tail)
+        }
+        (Some(_), None) => false,
+    }
+}
+
+#[cfg_attr(not(feature = "regex"), allow(dead_code))]
+fn to_pointer(path: &[Segment]) -> String {
+    let mut pointer = String::new();
+    for segment in path {
+        pointer.push('/');
+        match segment {
+            Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            Segment::Index(index) => {
+                let _ = write!(pointer, "{index}");
+            }
+        }
+    }
+    pointer
+}
+
+/// A segment of the path to the current value
+#[derive(Clone, Debug)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Tracks the path of the value being serialized, and the style overrides of the matching rules
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PathTracker {
+    rules: Vec<PathRule>,
+    path: Vec<Segment>,
+    /// the value styles in effect, with the path length they were set at
+    overrides: Vec<(usize, Style)>,
+}
+
+impl PathTracker {
+    pub(crate) fn add_rule(&mut self, rule: PathRule) {
+        self.rules.push(rule);
+    }
+
+    /// Without any rules, there is no need to track the path
+    pub(crate) fn is_active(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    pub(crate) fn downgrade(&mut self, depth: ColorDepth) {
+        for rule in &mut self.rules {
+            rule.downgrade(depth);
+        }
+    }
+
+    pub(crate) fn begin_object(&mut self) {
+        self.path.push(Segment::Key(String::new()));
+    }
+
+    pub(crate) fn begin_array(&mut self) {
+        self.path.push(Segment::Index(0));
+    }
+
+    pub(crate) fn end_container(&mut self) {
+        self.path.pop();
+    }
+
+    pub(crate) fn next_index(&mut self, first: bool) {
+        if let Some(Segment::Index(index)) = self.path.last_mut() {
+            if !first {
+                *index += 1;
+            }
+        }
+    }
+
+    /// Set the key of the current object entry, from its escaped JSON representation
+    pub(crate) fn set_key(&mut self, escaped: &str) {
+        if let Some(Segment::Key(key)) = self.path.last_mut() {
+            key.clear();
+            unescape(escaped, key);
+        }
+    }
+
+    /// The style of the current key, if overridden
+    pub(crate) fn key_style(&self) -> Option<Style> {
+        self.find_style(|rule| rule.key_style)
+    }
+
+    /// Enter a value, returns the style overriding the style of the value
+    pub(crate) fn begin_value(&mut self) -> Option<Style> {
+        if let Some(style) = self.find_style(|rule| rule.value_style) {
+            self.overrides.push((self.path.len(), style));
+        }
+        self.value_style()
+    }
+
+    /// Leave a value, returns the style overriding the style of the enclosing value
+    pub(crate) fn end_value(&mut self) -> Option<Style> {
+        if let Some((len, _)) = self.overrides.last() {
+            if *len == self.path.len() {
+                self.overrides.pop();
+            }
+        }
+        self.value_style()
+    }
+
+    fn value_style(&self) -> Option<Style> {
+        self.overrides.last().map(|(_, style)| *style)
+    }
+
+    fn find_style<F>(&self, f: F) -> Option<Style>
+    where
+        F: Fn(&PathRule) -> Option<Style>,
+    {
+        let mut pointer = None;
+        self.rules
+            .iter()
+            .rev()
+            .filter_map(|rule| f(rule).map(|style| (rule, style)))
+            .find(|(rule, _)| rule.is_match(&self.path, &mut pointer))
+            .map(|(_, style)| style)
+    }
+}
+
+/// Decode the escape sequences of a JSON string, invalid sequences are kept as they are
+pub(crate) fn unescape(escaped: &str, out: &mut String) {
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('u') => {
+                let hex = chars
+                    .as_str()
+                    .get(..4)
+                    .and_then(|hex| u16::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(unit) => {
+                        chars.nth(3);
+                        out.push(decode_utf16(unit, &mut chars));
+                    }
+                    None => out.push_str("\\u"),
+                }
+            }
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+}
+
+/// Decode a UTF-16 code unit, consuming the low surrogate of a pair from the input
+fn decode_utf16(unit: u16, chars: &mut std::str::Chars<'_>) -> char {
+    let rest = chars.as_str();
+    let low = rest
+        .strip_prefix("\\u")
+        .and_then(|rest| rest.get(..4))
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok());
+
+    if let Some(low) = low {
+        if let Some(Ok(c)) = char::decode_utf16([unit, low]).next() {
+            if c.len_utf16() == 2 {
+                chars.nth(5);
+                return c;
+            }
+        }
+    }
+
+    char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
@@ -0,0 +1,751 @@
+use crate::lexer::{self, Lexer, StringPart, TokenKind};
+use crate::path::unescape;
+use crate::{truncate, ColoredFormatter, Highlighter};
+use serde_json::ser::{CharEscape, CompactFormatter, Formatter};
+use std::io::{self, Write};
+
+/// Drives a [`ColoredFormatter`], sorting the keys of objects and truncating on the way
+///
+/// The calls for the entries of an open object are recorded, until the object ends and its
+/// entries can be replayed in order. So only the entries of the open objects are buffered, and
+/// the formatter and its highlighter see the calls as if the value was sorted to begin with.
+///
+/// Content beyond the limits is dropped as it comes, only counting what is elided for the
+/// markers.
+pub(crate) struct Shaper<F, H>
+where
+    F: Formatter,
+{
+    inner: ColoredFormatter<F, H>,
+    /// the open arrays and objects
+    stack: Vec<Container>,
+    /// an object key is being written
+    in_key: bool,
+    /// the string value being truncated
+    cut: Option<Cut>,
+}
+
+/// An open array or object
+#[derive(Default)]
+struct Container {
+    object: bool,
+    /// the number of entries written
+    items: usize,
+    /// the number of entries beyond the maximum number of items
+    more: usize,
+    /// the current entry is beyond the maximum number of items
+    dropping: bool,
+    /// the container is beyond the maximum depth, and only gets a marker
+    collapsed: bool,
+    /// the container is part of something dropped
+    ignored: bool,
+    /// the entries of an object, if its keys get sorted
+    entries: Option<Vec<Entry>>,
+}
+
+/// The state of truncating a string value
+struct Cut {
+    /// the number of characters left to write
+    remaining: usize,
+    /// the number of bytes elided, as escaped in JSON
+    elided: usize,
+}
+
+/// An entry of an object, the recorded calls for its key and value
+#[derive(Default)]
+struct Entry {
+    events: Vec<Event>,
+}
+
+impl Entry {
+    /// The unescaped key, for sorting
+    fn key(&self) -> String {
+        let mut escaped = Vec::new();
+        for event in &self.events {
+            if let Event::EndObjectKey = event {
+                break;
+            }
+            // writing to a `Vec` doesn't fail
+            let _ = write_key_part(&mut escaped, event);
+        }
+        // surrogate pairs may be split over two escapes, so they are decoded as a whole
+        let mut key = String::with_capacity(escaped.len());
+        unescape(&String::from_utf8_lossy(&escaped), &mut key);
+        key
+    }
+}
+
+/// A recorded call of a [`Formatter`] method
+enum Event {
+    Null,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Number(String),
+    BeginString,
+    EndString,
+    Fragment(String),
+    CharEscape(CharEscape),
+    /// an escape sequence of JSON text
+    Escape(String),
+    BeginArray,
+    EndArray,
+    BeginArrayValue(bool),
+    EndArrayValue,
+    BeginObject,
+    EndObject,
+    BeginObjectKey(bool),
+    EndObjectKey,
+    BeginObjectValue,
+    EndObjectValue,
+    /// a marker for elided content
+    Elided(String),
+    /// the marker for the entries beyond the maximum number of items
+    MoreItems {
+        object: bool,
+        first: bool,
+        count: usize,
+    },
+}
+
+impl<F, H> Shaper<F, H>
+where
+    F: Formatter,
+    H: Highlighter,
+{
+    pub(crate) fn new(inner: ColoredFormatter<F, H>) -> Self {
+        Shaper {
+            inner,
+            stack: Vec::new(),
+            in_key: false,
+            cut: None,
+        }
+    }
+
+    /// Whether the current position is beyond the limits
+    fn dropped(&self) -> bool {
+        self.stack
+            .last()
+            .is_some_and(|c| c.ignored || c.collapsed || c.dropping)
+    }
+
+    /// Emit a call, unless it is beyond the limits
+    fn pass<W>(&mut self, writer: &mut W, event: Event) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if self.dropped() {
+            return Ok(());
+        }
+        self.emit(writer, event)
+    }
+
+    /// Record a call for the entry being sorted, or pass it on to the formatter
+    fn emit<W>(&mut self, writer: &mut W, event: Event) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let entry = self
+            .stack
+            .iter_mut()
+            .rev()
+            .find_map(|container| container.entries.as_mut()?.last_mut());
+        match entry {
+            Some(entry) => {
+                entry.events.push(event);
+                Ok(())
+            }
+            None => self.replay(writer, event),
+        }
+    }
+
+    fn replay<W>(&mut self, writer: &mut W, event: Event) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let f = &mut self.inner;
+        match event {
+            Event::Null => f.write_null(writer),
+            Event::Bool(value) => f.write_bool(writer, value),
+            Event::I8(value) => f.write_i8(writer, value),
+            Event::I16(value) => f.write_i16(writer, value),
+            Event::I32(value) => f.write_i32(writer, value),
+            Event::I64(value) => f.write_i64(writer, value),
+            Event::I128(value) => f.write_i128(writer, value),
+            Event::U8(value) => f.write_u8(writer, value),
+            Event::U16(value) => f.write_u16(writer, value),
+            Event::U32(value) => f.write_u32(writer, value),
+            Event::U64(value) => f.write_u64(writer, value),
+            Event::U128(value) => f.write_u128(writer, value),
+            Event::F32(value) => f.write_f32(writer, value),
+            Event::F64(value) => f.write_f64(writer, value),
+            Event::Number(value) => f.write_number_str(writer, &value),
+            Event::BeginString => f.begin_string(writer),
+            Event::EndString => f.end_string(writer),
+            Event::Fragment(fragment) => f.write_string_fragment(writer, &fragment),
+            Event::CharEscape(char_escape) => f.write_char_escape(writer, char_escape),
+            Event::Escape(escape) => f.write_escape(writer, &escape),
+            Event::BeginArray => f.begin_array(writer),
+            Event::EndArray => f.end_array(writer),
+            Event::BeginArrayValue(first) => f.begin_array_value(writer, first),
+            Event::EndArrayValue => f.end_array_value(writer),
+            Event::BeginObject => f.begin_object(writer),
+            Event::EndObject => f.end_object(writer),
+            Event::BeginObjectKey(first) => f.begin_object_key(writer, first),
+            Event::EndObjectKey => f.end_object_key(writer),
+            Event::BeginObjectValue => f.begin_object_value(writer),
+            Event::EndObjectValue => f.end_object_value(writer),
+            Event::Elided(marker) => f.write_elided(writer, &marker),
+            Event::MoreItems {
+                object,
+                first,
+                count,
+            } => f.write_more_items(writer, object, first, count),
+        }
+    }
+
+    fn begin_container<W>(&mut self, writer: &mut W, object: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if self.dropped() {
+            self.stack.push(Container {
+                object,
+                ignored: true,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+        if self
+            .inner
+            .limits
+            .max_depth
+            .is_some_and(|max| self.stack.len() >= max)
+        {
+            self.stack.push(Container {
+                object,
+                collapsed: true,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        self.emit(writer, begin_event(object))?;
+        self.stack.push(Container {
+            object,
+            entries: (object && self.inner.sort_keys).then(Vec::new),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn end_container<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let Some(container) = self.stack.pop() else {
+            return Ok(());
+        };
+        let object = container.object;
+        if container.ignored {
+            return Ok(());
+        }
+        if container.collapsed {
+            // nothing is elided from empty arrays and objects
+            if container.items == 0 {
+                self.emit(writer, begin_event(object))?;
+                return self.emit(writer, end_event(object));
+            }
+            let marker = if object { "{…}" } else { "[…]" };
+            return self.emit(writer, Event::Elided(marker.into()));
+        }
+
+        let (written, more) = match container.entries {
+            Some(mut entries) => {
+                // a stable sort, so duplicate keys keep their order
+                entries.sort_by_cached_key(Entry::key);
+                let written = self
+                    .inner
+                    .limits
+                    .max_items
+                    .map_or(entries.len(), |max| max.min(entries.len()));
+                let more = entries.len() - written;
+                for (i, entry) in entries.into_iter().take(written).enumerate() {
+                    self.emit(writer, Event::BeginObjectKey(i == 0))?;
+                    for event in entry.events {
+                        self.emit(writer, event)?;
+                    }
+                }
+                (written, more)
+            }
+            None => (container.items, container.more),
+        };
+        if more > 0 {
+            let event = Event::MoreItems {
+                object,
+                first: written == 0,
+                count: more,
+            };
+            self.emit(writer, event)?;
+        }
+        self.emit(writer, end_event(object))
+    }
+
+    fn begin_entry<W>(&mut self, writer: &mut W, event: Event) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let max_items = self.inner.limits.max_items;
+        let Some(container) = self.stack.last_mut() else {
+            return self.emit(writer, event);
+        };
+        if container.ignored {
+            return Ok(());
+        }
+        if container.collapsed {
+            container.items += 1;
+            return Ok(());
+        }
+        if let Some(entries) = &mut container.entries {
+            // whether the entry comes first, and if it is written at all, is known once the
+            // object ends
+            entries.push(Entry::default());
+            return Ok(());
+        }
+
+        container.dropping = max_items.is_some_and(|max| container.items >= max);
+        if container.dropping {
+            container.more += 1;
+            return Ok(());
+        }
+        container.items += 1;
+        self.emit(writer, event)
+    }
+
+    /// Whether to keep an escape sequence of a string value, with its length in JSON
+    fn keep_escape(&mut self, len: usize) -> bool {
+        match &mut self.cut {
+            Some(cut) if cut.remaining == 0 => {
+                cut.elided += len;
+                false
+            }
+            Some(cut) => {
+                cut.remaining -= 1;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Write JSON text, which must be valid, token by token
+    pub(crate) fn write_tokens<W>(&mut self, writer: &mut W, json: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        // the open containers, `true` for objects, with a flag if the next entry is the first
+        let mut stack: Vec<(bool, bool)> = Vec::new();
+
+        for (kind, token) in Lexer::new(json) {
+            // begin the entry of an object with its key, values of objects are started by the
+            // colon
+            let begins_entry = match kind {
+                TokenKind::Whitespace
+                | TokenKind::Comma
+                | TokenKind::Colon
+                | TokenKind::EndObject
+                | TokenKind::EndArray => false,
+                TokenKind::Key => true,
+                _ => matches!(stack.last(), Some((false, _))),
+            };
+            if begins_entry {
+                if let Some((object, first)) = stack.last_mut() {
+                    let first = std::mem::take(first);
+                    if *object {
+                        self.begin_object_key(writer, first)?;
+                    } else {
+                        self.begin_array_value(writer, first)?;
+                    }
+                }
+            }
+
+            match kind {
+                TokenKind::Whitespace | TokenKind::Comma => continue,
+                TokenKind::Key => {
+                    self.write_string_token(writer, token)?;
+                    self.end_object_key(writer)?;
+                    continue;
+                }
+                TokenKind::Colon => {
+                    self.begin_object_value(writer)?;
+                    continue;
+                }
+                TokenKind::BeginObject => {
+                    self.begin_object(writer)?;
+                    stack.push((true, true));
+                    continue;
+                }
+                TokenKind::BeginArray => {
+                    self.begin_array(writer)?;
+                    stack.push((false, true));
+                    continue;
+                }
+                TokenKind::EndObject => {
+                    stack.pop();
+                    self.end_object(writer)?;
+                }
+                TokenKind::EndArray => {
+                    stack.pop();
+                    self.end_array(writer)?;
+                }
+                TokenKind::String => self.write_string_token(writer, token)?,
+                TokenKind::Integer | TokenKind::Float => self.write_number_str(writer, token)?,
+                TokenKind::Bool => self.write_bool(writer, token == "true")?,
+                TokenKind::Null => self.write_null(writer)?,
+                TokenKind::Error => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid JSON"));
+                }
+            }
+
+            // a value is complete
+            match stack.last() {
+                Some((true, _)) => self.end_object_value(writer)?,
+                Some((false, _)) => self.end_array_value(writer)?,
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a string token of raw JSON, including the quotes, as if it was serialized
+    fn write_string_token<W>(&mut self, writer: &mut W, token: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_string(writer)?;
+        for part in lexer::string_parts(&token[1..token.len() - 1]) {
+            match part {
+                StringPart::Fragment(fragment) => self.write_string_fragment(writer, fragment)?,
+                StringPart::Escape(escape) => {
+                    if !self.dropped() && self.keep_escape(escape.len()) {
+                        self.emit(writer, Event::Escape(escape.into()))?;
+                    }
+                }
+            }
+        }
+        self.end_string(writer)
+    }
+}
+
+impl<F, H> Formatter for Shaper<F, H>
+where
+    F: Formatter,
+    H: Highlighter,
+{
+    fn write_null<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::Null)
+    }
+
+    fn write_bool<W>(&mut self, writer: &mut W, value: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::Bool(value))
+    }
+
+    fn write_i8<W>(&mut self, writer: &mut W, value: i8) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::I8(value))
+    }
+
+    fn write_i16<W>(&mut self, writer: &mut W, value: i16) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::I16(value))
+    }
+
+    fn write_i32<W>(&mut self, writer: &mut W, value: i32) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::I32(value))
+    }
+
+    fn write_i64<W>(&mut self, writer: &mut W, value: i64) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::I64(value))
+    }
+
+    fn write_i128<W>(&mut self, writer: &mut W, value: i128) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::I128(value))
+    }
+
+    fn write_u8<W>(&mut self, writer: &mut W, value: u8) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::U8(value))
+    }
+
+    fn write_u16<W>(&mut self, writer: &mut W, value: u16) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::U16(value))
+    }
+
+    fn write_u32<W>(&mut self, writer: &mut W, value: u32) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::U32(value))
+    }
+
+    fn write_u64<W>(&mut self, writer: &mut W, value: u64) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::U64(value))
+    }
+
+    fn write_u128<W>(&mut self, writer: &mut W, value: u128) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::U128(value))
+    }
+
+    fn write_f32<W>(&mut self, writer: &mut W, value: f32) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::F32(value))
+    }
+
+    fn write_f64<W>(&mut self, writer: &mut W, value: f64) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::F64(value))
+    }
+
+    fn write_number_str<W>(&mut self, writer: &mut W, value: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::Number(value.into()))
+    }
+
+    fn begin_string<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if !self.dropped() && !self.in_key {
+            self.cut = self.inner.limits.max_string_length.map(|max| Cut {
+                remaining: max,
+                elided: 0,
+            });
+        }
+        self.pass(writer, Event::BeginString)
+    }
+
+    fn end_string<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if let Some(cut) = self.cut.take().filter(|cut| cut.elided > 0) {
+            let marker = truncate::elided_string(cut.elided);
+            self.pass(writer, Event::Elided(marker))?;
+        }
+        self.pass(writer, Event::EndString)
+    }
+
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let mut fragment = fragment;
+        if let Some(cut) = &mut self.cut {
+            match fragment.char_indices().nth(cut.remaining) {
+                Some((index, _)) => {
+                    cut.elided += fragment.len() - index;
+                    cut.remaining = 0;
+                    fragment = &fragment[..index];
+                }
+                None => cut.remaining -= fragment.chars().count(),
+            }
+            if fragment.is_empty() {
+                return Ok(());
+            }
+        }
+        self.pass(writer, Event::Fragment(fragment.into()))
+    }
+
+    fn write_char_escape<W>(&mut self, writer: &mut W, char_escape: CharEscape) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let len = match char_escape {
+            CharEscape::AsciiControl(_) => 6,
+            _ => 2,
+        };
+        if self.dropped() || !self.keep_escape(len) {
+            return Ok(());
+        }
+        self.emit(writer, Event::CharEscape(char_escape))
+    }
+
+    fn begin_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_container(writer, false)
+    }
+
+    fn end_array<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.end_container(writer)
+    }
+
+    fn begin_array_value<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_entry(writer, Event::BeginArrayValue(first))
+    }
+
+    fn end_array_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::EndArrayValue)
+    }
+
+    fn begin_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.begin_container(writer, true)
+    }
+
+    fn end_object<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.end_container(writer)
+    }
+
+    fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.in_key = true;
+        self.begin_entry(writer, Event::BeginObjectKey(first))
+    }
+
+    fn end_object_key<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.in_key = false;
+        self.pass(writer, Event::EndObjectKey)
+    }
+
+    fn begin_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::BeginObjectValue)
+    }
+
+    fn end_object_value<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.pass(writer, Event::EndObjectValue)
+    }
+
+    fn write_raw_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        // raw values get sorted and truncated like everything else
+        self.write_tokens(writer, fragment)
+    }
+}
+
+/// Write the escaped text of a recorded call for a key, numbers are keys too
+fn write_key_part(out: &mut Vec<u8>, event: &Event) -> io::Result<()> {
+    let mut f = CompactFormatter;
+    match event {
+        Event::I8(value) => f.write_i8(out, *value),
+        Event::I16(value) => f.write_i16(out, *value),
+        Event::I32(value) => f.write_i32(out, *value),
+        Event::I64(value) => f.write_i64(out, *value),
+        Event::I128(value) => f.write_i128(out, *value),
+        Event::U8(value) => f.write_u8(out, *value),
+        Event::U16(value) => f.write_u16(out, *value),
+        Event::U32(value) => f.write_u32(out, *value),
+        Event::U64(value) => f.write_u64(out, *value),
+        Event::U128(value) => f.write_u128(out, *value),
+        Event::F32(value) => f.write_f32(out, *value),
+        Event::F64(value) => f.write_f64(out, *value),
+        Event::Number(text) | Event::Fragment(text) | Event::Escape(text) => {
+            f.write_string_fragment(out, text)
+        }
+        Event::CharEscape(char_escape) => {
+            let escape = match char_escape {
+                CharEscape::Quote => "\\\"",
+                CharEscape::ReverseSolidus => "\\\\",
+                CharEscape::Solidus => "\\/",
+                CharEscape::Backspace => "\\b",
+                CharEscape::FormFeed => "\\f",
+                CharEscape::LineFeed => "\\n",
+                CharEscape::CarriageReturn => "\\r",
+                CharEscape::Tab => "\\t",
+                CharEscape::AsciiControl(byte) => return write!(out, "\\u{byte:04x}"),
+            };
+            f.write_string_fragment(out, escape)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn begin_event(object: bool) -> Event {
+    if object {
+        Event::BeginObject
+    } else {
+        Event::BeginArray
+    }
+}
+
+fn end_event(object: bool) -> Event {
+    if object {
+        Event::EndObject
+    } else {
+        Event::EndArray
+    }
+}
@@ -0,0 +1,91 @@
+use crate::lexer::{Lexer, TokenKind};
+use crate::path::unescape;
+use std::iter::Peekable;
+
+/// A value of a JSON document, referencing the text of its tokens
+pub(crate) enum Node<'a> {
+    Scalar(&'a str),
+    Array(Vec<Node<'a>>),
+    /// the entries, with the key as written, and unescaped for sorting
+    Object(Vec<(&'a str, String, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    /// Parse JSON text, which must be valid, optionally sorting the keys of all objects
+    pub(crate) fn parse(json: &'a str, sort: bool) -> Option<Self> {
+        let mut tokens = Lexer::new(json)
+            .filter(|(kind, _)| {
+                !matches!(
+                    kind,
+                    TokenKind::Whitespace | TokenKind::Comma | TokenKind::Colon
+                )
+            })
+            .peekable();
+        parse(&mut tokens, sort)
+    }
+
+    /// Write the value as compact JSON text
+    pub(crate) fn write(&self, out: &mut String) {
+        match self {
+            Node::Scalar(token) => out.push_str(token),
+            Node::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write(out);
+                }
+                out.push(']');
+            }
+            Node::Object(entries) => {
+                out.push('{');
+                for (i, (key, _, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(key);
+                    out.push(':');
+                    value.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn parse<'a, I>(tokens: &mut Peekable<I>, sort: bool) -> Option<Node<'a>>
+where
+    I: Iterator<Item = (TokenKind, &'a str)>,
+{
+    let node = match tokens.next()? {
+        (TokenKind::BeginArray, _) => {
+            let mut values = Vec::new();
+            while tokens
+                .next_if(|(kind, _)| *kind == TokenKind::EndArray)
+                .is_none()
+            {
+                values.push(parse(tokens, sort)?);
+            }
+            Node::Array(values)
+        }
+        (TokenKind::BeginObject, _) => {
+            let mut entries = Vec::new();
+            while tokens
+                .next_if(|(kind, _)| *kind == TokenKind::EndObject)
+                .is_none()
+            {
+                let (_, key) = tokens.next()?;
+                let mut unescaped = String::new();
+                unescape(&key[1..key.len() - 1], &mut unescaped);
+                entries.push((key, unescaped, parse(tokens, sort)?));
+            }
+            if sort {
+                entries.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+            }
+            Node::Object(entries)
+        }
+        (_, token) => Node::Scalar(token),
+    };
+    Some(node)
+}
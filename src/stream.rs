@@ -0,0 +1,232 @@
+use crate::error::message;
+use crate::lexer::{Lexer, TokenKind};
+use crate::{AnsiHighlighter, ColorMode, ColoredFormatter, Highlighter, Output};
+use serde::de::IgnoredAny;
+use serde_json::ser::Formatter;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// Colorizes a stream of JSON values, like newline delimited JSON (JSON Lines)
+///
+/// Each value is written as soon as it was read. A value may span multiple lines, as long as the
+/// nested lines don't start with a bracket, like it is the case for indented JSON. Invalid input
+/// doesn't abort the stream, but gets reported and skipped.
+///
+/// ```rust
+/// # use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, StreamColorizer};
+/// # fn main() -> std::io::Result<()> {
+/// let input = "{\"level\": \"info\"}\n{\"level\": \n{\"level\": \"warn\"}\n";
+/// let mut output = Vec::new();
+///
+/// StreamColorizer::new(ColoredFormatter::new(CompactFormatter {}))
+///     .with_mode(ColorMode::Off)
+///     .colorize(input.as_bytes(), &mut output, |err| eprintln!("{err}"))?;
+///
+/// assert_eq!(output, b"{\"level\":\"info\"}\n{\"level\":\"warn\"}\n");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct StreamColorizer<F, H = AnsiHighlighter>
+where
+    F: Formatter,
+{
+    formatter: ColoredFormatter<F, H>,
+    mode: ColorMode,
+    echo_invalid: bool,
+}
+
+impl<F, H> StreamColorizer<F, H>
+where
+    F: Formatter + Clone,
+    H: Highlighter + Clone,
+{
+    pub fn new(formatter: ColoredFormatter<F, H>) -> Self {
+        StreamColorizer {
+            formatter,
+            mode: ColorMode::Auto(Output::StdOut),
+            echo_invalid: false,
+        }
+    }
+
+    /// Set the color mode, defaults to [`ColorMode::Auto`] for stdout
+    pub fn with_mode(mut self, mode: ColorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Write invalid input as it is, instead of skipping it
+    pub fn with_echo_invalid(mut self, echo_invalid: bool) -> Self {
+        self.echo_invalid = echo_invalid;
+        self
+    }
+
+    /// Read all values from `reader`, writing them colored to `writer`, each followed by a newline
+    ///
+    /// Input which is not valid JSON is passed to `on_error`, processing continues with the next
+    /// line. Only failing to read or write aborts the stream.
+    pub fn colorize<R, W, E>(
+        &self,
+        mut reader: R,
+        writer: &mut W,
+        mut on_error: E,
+    ) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+        E: FnMut(LineError),
+    {
+        let mode = self.mode.eval();
+        let mut record = Record::default();
+        let mut line = Vec::new();
+
+        loop {
+            line.clear();
+            let eof = reader.read_until(b'\n', &mut line)? == 0;
+
+            // a line starting with a bracket begins a new value, so the pending one is truncated
+            if eof || (record.depth > 0 && matches!(line.first(), Some(b'{' | b'['))) {
+                self.write_record(&mut record, writer, mode, &mut on_error)?;
+            }
+            if eof {
+                return Ok(());
+            }
+
+            record.push(&line);
+            if record.depth <= 0 {
+                self.write_record(&mut record, writer, mode, &mut on_error)?;
+            }
+        }
+    }
+
+    fn write_record<W, E>(
+        &self,
+        record: &mut Record,
+        writer: &mut W,
+        mode: ColorMode,
+        on_error: &mut E,
+    ) -> io::Result<()>
+    where
+        W: Write,
+        E: FnMut(LineError),
+    {
+        // colorize the text of each value, which keeps the order of keys and the numbers
+        let mut values =
+            serde_json::Deserializer::from_slice(&record.text).into_iter::<IgnoredAny>();
+        let mut start = 0;
+
+        while let Some(value) = values.next() {
+            match value {
+                Ok(_) => {
+                    let end = values.byte_offset();
+                    let text = String::from_utf8_lossy(&record.text[start..end]);
+                    self.formatter
+                        .clone()
+                        .write_colored_str(text.trim(), writer, mode)?;
+                    writer.write_all(b"\n")?;
+                    start = end;
+                }
+                Err(error) => {
+                    let text = &record.text[values.byte_offset()..];
+                    if self.echo_invalid {
+                        writer.write_all(text)?;
+                        if !text.ends_with(b"\n") {
+                            writer.write_all(b"\n")?;
+                        }
+                    }
+                    on_error(LineError {
+                        line: record.line + error.line().saturating_sub(1),
+                        column: error.column(),
+                        text: String::from_utf8_lossy(text).trim_end().to_string(),
+                        error,
+                    });
+                    break;
+                }
+            }
+        }
+
+        record.clear();
+        Ok(())
+    }
+}
+
+/// The text of the value currently being read
+#[derive(Default)]
+struct Record {
+    text: Vec<u8>,
+    /// the number of the first line of the record
+    line: usize,
+    /// the number of lines read so far
+    lines: usize,
+    /// the nesting level at the end of the record
+    depth: isize,
+}
+
+impl Record {
+    fn push(&mut self, line: &[u8]) {
+        self.lines += 1;
+        if self.text.iter().all(u8::is_ascii_whitespace) {
+            self.text.clear();
+            self.line = self.lines;
+        }
+
+        self.text.extend_from_slice(line);
+        for (kind, _) in Lexer::new(&String::from_utf8_lossy(line)) {
+            match kind {
+                TokenKind::BeginObject | TokenKind::BeginArray => self.depth += 1,
+                TokenKind::EndObject | TokenKind::EndArray => self.depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.depth = 0;
+    }
+}
+
+/// Invalid input, found in a stream of JSON values
+#[derive(Debug)]
+pub struct LineError {
+    line: usize,
+    column: usize,
+    text: String,
+    error: serde_json::Error,
+}
+
+impl LineError {
+    /// The line of the input the error was found in, starting at 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column in the line the error was found at, starting at 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The invalid input, starting after the last valid value
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The error reported by the parser
+    pub fn error(&self) -> &serde_json::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the location reported by the parser is relative to the start of the value
+        let message = message(&self.error);
+        write!(f, "{message} at line {} column {}", self.line, self.column)
+    }
+}
+
+impl std::error::Error for LineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
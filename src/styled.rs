@@ -0,0 +1,189 @@
+use crate::highlight::{Highlight, Highlighter, Role};
+use std::io;
+use yansi::{Paint, Style};
+
+/// Creates [`StyledWriter`]s, applying an optional override to all styles.
+#[derive(Clone)]
+pub(crate) struct Painter<H> {
+    pub(crate) highlighter: H,
+    /// a style replacing the style of everything written
+    pub(crate) style_override: Option<Style>,
+    /// while capturing, output is collected unstyled, instead of being written
+    capture: Option<Vec<u8>>,
+    /// write everything without any styles
+    pub(crate) plain: bool,
+}
+
+impl<H> Painter<H>
+where
+    H: Highlighter,
+{
+    pub(crate) fn new(highlighter: H) -> Self {
+        Painter {
+            highlighter,
+            style_override: None,
+            capture: None,
+            plain: false,
+        }
+    }
+
+    /// Replace the highlighter, keeping the state
+    pub(crate) fn with_highlighter<H2>(self, highlighter: H2) -> Painter<H2> {
+        Painter {
+            highlighter,
+            style_override: self.style_override,
+            capture: self.capture,
+            plain: self.plain,
+        }
+    }
+
+    pub(crate) fn writer<'a, W>(
+        &'a mut self,
+        writer: &'a mut W,
+        role: Role,
+        style: Style,
+    ) -> StyledWriter<'a, W, H>
+    where
+        W: ?Sized + io::Write,
+    {
+        StyledWriter {
+            writer,
+            token: self.highlight(role, style),
+            enabled: self.is_enabled(),
+            highlighter: &mut self.highlighter,
+            started: false,
+            capture: self.capture.as_mut(),
+        }
+    }
+
+    /// Begin a string or key, see [`Highlighter::begin_string`]
+    pub(crate) fn begin_string<W>(
+        &mut self,
+        writer: &mut W,
+        role: Role,
+        style: Style,
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if self.capture.is_some() || !self.is_enabled() {
+            return Ok(());
+        }
+        let token = self.highlight(role, style);
+        self.highlighter.begin_string(writer, token)
+    }
+
+    /// End a string or key, see [`Highlighter::end_string`]
+    pub(crate) fn end_string<W>(
+        &mut self,
+        writer: &mut W,
+        role: Role,
+        style: Style,
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if self.capture.is_some() || !self.is_enabled() {
+            return Ok(());
+        }
+        let token = self.highlight(role, style);
+        self.highlighter.end_string(writer, token)
+    }
+
+    /// Start capturing the output, instead of writing it
+    pub(crate) fn start_capture(&mut self) {
+        self.capture = Some(Vec::new());
+    }
+
+    /// Stop capturing, returns the captured output, or `None` if not capturing
+    pub(crate) fn finish_capture(&mut self) -> Option<Vec<u8>> {
+        self.capture.take()
+    }
+
+    fn highlight(&self, role: Role, style: Style) -> Highlight {
+        Highlight {
+            role,
+            style: self.style_override.unwrap_or(style),
+            overridden: self.style_override.is_some(),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.plain && (Paint::is_enabled() || !self.highlighter.is_terminal())
+    }
+}
+
+/// A writer, wrapping everything written to it into the markup of a token, like escape codes.
+///
+/// The prefix is written lazily, on the first non-empty write, and the suffix only if a prefix
+/// was written. So nothing is emitted for empty tokens, and no intermediate buffer is needed.
+pub(crate) struct StyledWriter<'a, W, H>
+where
+    W: ?Sized + io::Write,
+{
+    writer: &'a mut W,
+    highlighter: &'a mut H,
+    token: Highlight,
+    enabled: bool,
+    started: bool,
+    capture: Option<&'a mut Vec<u8>>,
+}
+
+impl<'a, W, H> StyledWriter<'a, W, H>
+where
+    W: ?Sized + io::Write,
+    H: Highlighter,
+{
+    /// Finish the styled section, writing the suffix if required.
+    pub(crate) fn finish(self) -> io::Result<()> {
+        if self.started {
+            self.highlighter.end_token(self.writer, self.token)?;
+        }
+        Ok(())
+    }
+
+    /// Prepare for writing non-empty content, returns `false` if the content must be dropped.
+    fn start(&mut self) -> io::Result<bool> {
+        if !self.enabled {
+            // same as yansi: masked content is dropped when painting is disabled
+            return Ok(!self.token.style.is_masked());
+        }
+
+        if !self.started {
+            self.highlighter.begin_token(self.writer, self.token)?;
+            self.started = true;
+        }
+
+        Ok(true)
+    }
+}
+
+impl<W, H> io::Write for StyledWriter<'_, W, H>
+where
+    W: ?Sized + io::Write,
+    H: Highlighter,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if let Some(capture) = &mut self.capture {
+            capture.extend_from_slice(buf);
+            return Ok(());
+        }
+        if buf.is_empty() || !self.start()? {
+            return Ok(());
+        }
+        if self.enabled {
+            self.highlighter.write_text(self.writer, buf)
+        } else {
+            self.writer.write_all(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{ColorMode, ColoredFormatter, PrettyFormatter, Styler};
+use serde::Serialize;
+use std::cell::Cell;
+use std::fmt;
+use tracing_core::field::{Field, Visit};
+use tracing_subscriber::field::{MakeVisitor, VisitFmt, VisitOutput};
+use tracing_subscriber::fmt::format::{DefaultVisitor, Writer};
+
+thread_local! {
+    /// The styler, and if ANSI escape codes may be used, while [`JsonFields`] formats a field
+    static CONTEXT: Cell<Option<(Styler, bool)>> = const { Cell::new(None) };
+}
+
+/// A value, which is formatted as JSON
+///
+/// Used as a field of a `tracing` event, formatted by [`JsonFields`], the value is pretty printed
+/// with the styler of the field formatter. It is colored if the subscriber uses ANSI escape
+/// codes, see `with_ansi` of the `tracing_subscriber` builders.
+///
+/// Elsewhere, e.g. with other field formatters or with `log`, the value is written without
+/// color: pretty printed by `Display`, and compact by `Debug`.
+///
+/// ```rust
+/// # use colored_json::ColoredJson;
+/// # use serde_json::json;
+/// let value = json!({"a": [1, 2]});
+/// assert_eq!(ColoredJson(&value).to_string(), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+/// assert_eq!(format!("{:?}", ColoredJson(&value)), r#"{"a":[1,2]}"#);
+/// ```
+#[derive(Clone, Copy)]
+pub struct ColoredJson<'a, T: ?Sized>(pub &'a T);
+
+impl<T> ColoredJson<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    /// Write the value pretty printed, if formatted by [`JsonFields`]
+    fn fmt_field(&self, f: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        let (styler, ansi) = CONTEXT.with(Cell::get)?;
+        let mode = if ansi { ColorMode::On } else { ColorMode::Off };
+        let s = ColoredFormatter::with_styler(PrettyFormatter::new(), styler)
+            .to_colored_json(self.0, mode)
+            .map_err(|_| fmt::Error);
+        Some(s.and_then(|s| f.write_str(&s)))
+    }
+}
+
+impl<T> fmt::Display for ColoredJson<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(result) = self.fmt_field(f) {
+            return result;
+        }
+        let s = serde_json::to_string_pretty(self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&s)
+    }
+}
+
+impl<T> fmt::Debug for ColoredJson<'_, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(result) = self.fmt_field(f) {
+            return result;
+        }
+        let s = serde_json::to_string(self.0).map_err(|_| fmt::Error)?;
+        f.write_str(&s)
+    }
+}
+
+/// A `tracing_subscriber` field formatter, pretty printing [`ColoredJson`] fields
+///
+/// All other fields are formatted like by the default field formatter of `tracing_subscriber`.
+/// JSON is colored with the styler if the subscriber uses ANSI escape codes, regardless of the
+/// [`ColorMode`].
+///
+/// ```rust
+/// # use colored_json::{JsonFields, Styler};
+/// let subscriber = tracing_subscriber::fmt()
+///     .fmt_fields(JsonFields::new().with_styler(Styler::default()))
+///     .finish();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonFields {
+    styler: Styler,
+}
+
+impl JsonFields {
+    pub fn new() -> Self {
+        JsonFields::default()
+    }
+
+    /// Set the styler to color JSON fields with
+    pub fn with_styler(mut self, styler: Styler) -> Self {
+        self.styler = styler;
+        self
+    }
+}
+
+impl<'a> MakeVisitor<Writer<'a>> for JsonFields {
+    type Visitor = JsonVisitor<'a>;
+
+    fn make_visitor(&self, target: Writer<'a>) -> Self::Visitor {
+        JsonVisitor {
+            context: (self.styler, target.has_ansi_escapes()),
+            inner: DefaultVisitor::new(target, true),
+        }
+    }
+}
+
+/// The visitor of [`JsonFields`], formatting fields into a [`Writer`]
+pub struct JsonVisitor<'a> {
+    context: (Styler, bool),
+    inner: DefaultVisitor<'a>,
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.inner.record_str(field, value);
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.inner.record_error(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        // the value can't be inspected, so a `ColoredJson` finds the context of the field itself
+        let previous = CONTEXT.with(|context| context.replace(Some(self.context)));
+        self.inner.record_debug(field, value);
+        CONTEXT.with(|context| context.set(previous));
+    }
+}
+
+impl VisitOutput<fmt::Result> for JsonVisitor<'_> {
+    fn finish(self) -> fmt::Result {
+        self.inner.finish()
+    }
+}
+
+impl VisitFmt for JsonVisitor<'_> {
+    fn writer(&mut self) -> &mut dyn fmt::Write {
+        self.inner.writer()
+    }
+}
@@ -0,0 +1,247 @@
+use crate::Styler;
+use yansi::{Color, Style};
+
+/// The kind of terminal background a theme is made for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Background {
+    /// Works with dark and light backgrounds
+    Any,
+    /// Made for dark backgrounds
+    Dark,
+    /// Made for light backgrounds
+    Light,
+}
+
+/// A named, built-in preset for a [`Styler`]
+///
+/// ```rust
+/// # use colored_json::{Styler, Theme};
+/// for theme in Theme::all() {
+///     println!("{:20} {}", theme.name(), theme.description());
+/// }
+///
+/// let styler = Styler::from_theme("solarized-dark").expect("known theme");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    name: &'static str,
+    description: &'static str,
+    background: Background,
+    styler: fn() -> Styler,
+}
+
+impl Theme {
+    /// All built-in themes, the first one is the default theme
+    pub fn all() -> &'static [Theme] {
+        THEMES
+    }
+
+    /// The names of all built-in themes, e.g. to be used as possible values for a `--theme` flag
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        THEMES.iter().map(|theme| theme.name)
+    }
+
+    /// Find a built-in theme by its name
+    pub fn find(name: &str) -> Option<&'static Theme> {
+        THEMES.iter().find(|theme| theme.name == name)
+    }
+
+    /// The name of the theme
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// A short, human readable description of the theme
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// The kind of background the theme is made for
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Create the styler of the theme
+    pub fn styler(&self) -> Styler {
+        (self.styler)()
+    }
+}
+
+impl Styler {
+    /// Create a styler from a built-in theme, returns `None` if no theme with this name exists
+    ///
+    /// See [`Theme::names`] for a list of all names.
+    pub fn from_theme(name: &str) -> Option<Styler> {
+        Theme::find(name).map(Theme::styler)
+    }
+}
+
+const THEMES: &[Theme] = &[
+    Theme {
+        name: "jq",
+        description: "The default theme, resembling the jq style",
+        background: Background::Any,
+        styler: Styler::default,
+    },
+    Theme {
+        name: "jless",
+        description: "Resembling the jless style",
+        background: Background::Any,
+        styler: jless,
+    },
+    Theme {
+        name: "bat",
+        description: "Monokai colors, resembling the default theme of bat",
+        background: Background::Dark,
+        styler: bat,
+    },
+    Theme {
+        name: "solarized-dark",
+        description: "Solarized, for dark backgrounds",
+        background: Background::Dark,
+        styler: solarized_dark,
+    },
+    Theme {
+        name: "solarized-light",
+        description: "Solarized, for light backgrounds",
+        background: Background::Light,
+        styler: solarized_light,
+    },
+    Theme {
+        name: "high-contrast",
+        description: "Bold, basic colors for dark backgrounds",
+        background: Background::Dark,
+        styler: high_contrast,
+    },
+    Theme {
+        name: "high-contrast-light",
+        description: "Bold, basic colors for light backgrounds",
+        background: Background::Light,
+        styler: high_contrast_light,
+    },
+    Theme {
+        name: "colorblind",
+        description: "Colorblind safe colors, based on the Okabe-Ito palette",
+        background: Background::Any,
+        styler: colorblind,
+    },
+];
+
+fn jless() -> Styler {
+    Styler {
+        object_brackets: Style::default(),
+        array_brackets: Style::default(),
+        key: Style::new(Color::Blue).bold(),
+        string_value: Style::new(Color::Green),
+        integer_value: Style::new(Color::Magenta),
+        float_value: Style::new(Color::Magenta),
+        bool_value: Style::new(Color::Yellow),
+        nil_value: Style::new(Color::Fixed(8)),
+        added: Style::new(Color::Green).bold(),
+        removed: Style::new(Color::Red).bold(),
+        changed: Style::new(Color::Cyan).bold(),
+        ..Default::default()
+    }
+}
+
+fn bat() -> Styler {
+    let white = Style::new(Color::RGB(248, 248, 242));
+    let purple = Style::new(Color::RGB(174, 129, 255));
+    Styler {
+        object_brackets: white,
+        object_colon: white,
+        array_brackets: white,
+        key: Style::new(Color::RGB(249, 38, 114)),
+        string_value: Style::new(Color::RGB(230, 219, 116)),
+        integer_value: purple,
+        float_value: purple,
+        bool_value: purple,
+        nil_value: purple,
+        added: Style::new(Color::RGB(166, 226, 46)),
+        removed: Style::new(Color::RGB(249, 38, 114)).bold(),
+        changed: Style::new(Color::RGB(253, 151, 31)),
+        ..Default::default()
+    }
+}
+
+fn solarized(content: Color, emphasis: Color) -> Styler {
+    let magenta = Style::new(Color::RGB(211, 54, 130));
+    Styler {
+        object_brackets: Style::new(emphasis),
+        object_colon: Style::new(content),
+        array_brackets: Style::new(emphasis),
+        key: Style::new(Color::RGB(38, 139, 210)),
+        string_value: Style::new(Color::RGB(42, 161, 152)),
+        integer_value: magenta,
+        float_value: magenta,
+        bool_value: Style::new(Color::RGB(181, 137, 0)),
+        nil_value: Style::new(Color::RGB(203, 75, 22)),
+        added: Style::new(Color::RGB(133, 153, 0)),
+        removed: Style::new(Color::RGB(220, 50, 47)),
+        // the yellow of solarized is taken by bools
+        changed: Style::new(Color::RGB(108, 113, 196)),
+        ..Default::default()
+    }
+}
+
+fn solarized_dark() -> Styler {
+    solarized(Color::RGB(131, 148, 150), Color::RGB(147, 161, 161))
+}
+
+fn solarized_light() -> Styler {
+    solarized(Color::RGB(101, 123, 131), Color::RGB(88, 110, 117))
+}
+
+fn high_contrast() -> Styler {
+    Styler {
+        object_brackets: Style::new(Color::White).bold(),
+        object_colon: Style::new(Color::White).bold(),
+        array_brackets: Style::new(Color::White).bold(),
+        key: Style::new(Color::Yellow).bold(),
+        string_value: Style::new(Color::Green).bold(),
+        integer_value: Style::new(Color::Cyan).bold(),
+        float_value: Style::new(Color::Cyan).bold(),
+        bool_value: Style::new(Color::Magenta).bold(),
+        nil_value: Style::new(Color::Red).bold(),
+        added: Style::new(Color::Green).bold().underline(),
+        removed: Style::new(Color::Red).bold().underline(),
+        changed: Style::new(Color::Yellow).bold().underline(),
+        ..Default::default()
+    }
+}
+
+fn high_contrast_light() -> Styler {
+    Styler {
+        object_brackets: Style::new(Color::Black).bold(),
+        object_colon: Style::new(Color::Black).bold(),
+        array_brackets: Style::new(Color::Black).bold(),
+        key: Style::new(Color::Blue).bold(),
+        string_value: Style::new(Color::Green).bold(),
+        integer_value: Style::new(Color::Magenta).bold(),
+        float_value: Style::new(Color::Magenta).bold(),
+        bool_value: Style::new(Color::Red).bold(),
+        nil_value: Style::new(Color::Black).bold(),
+        added: Style::new(Color::Green).bold().underline(),
+        removed: Style::new(Color::Red).bold().underline(),
+        // yellow is hard to read on light backgrounds
+        changed: Style::new(Color::Magenta).bold().underline(),
+        ..Default::default()
+    }
+}
+
+fn colorblind() -> Styler {
+    let purple = Style::new(Color::RGB(204, 121, 167));
+    Styler {
+        key: Style::new(Color::RGB(86, 180, 233)).bold(),
+        string_value: Style::new(Color::RGB(230, 159, 0)),
+        integer_value: purple,
+        float_value: purple,
+        bool_value: Style::new(Color::RGB(0, 158, 115)),
+        nil_value: Style::new(Color::RGB(0, 114, 178)),
+        // blue and vermillion, instead of green and red
+        added: Style::new(Color::RGB(0, 114, 178)).bold(),
+        removed: Style::new(Color::RGB(213, 94, 0)).bold(),
+        changed: Style::new(Color::RGB(204, 121, 167)).bold(),
+        ..Default::default()
+    }
+}
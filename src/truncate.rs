@@ -0,0 +1,46 @@
+/// Limits for truncating the output, `None` for no limit
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Limits {
+    /// the number of characters of string values
+    pub(crate) max_string_length: Option<usize>,
+    /// the number of entries of arrays and objects
+    pub(crate) max_items: Option<usize>,
+    /// the number of nested levels of arrays and objects
+    pub(crate) max_depth: Option<usize>,
+}
+
+impl Limits {
+    pub(crate) fn is_active(&self) -> bool {
+        self.max_string_length.is_some() || self.max_items.is_some() || self.max_depth.is_some()
+    }
+}
+
+/// The marker for the elided rest of a string, like `…(1.9 MB elided)`
+pub(crate) fn elided_string(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+
+    if bytes < 1000 {
+        return format!("…({bytes} B elided)");
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 999.95 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("…({size:.1} {} elided)", UNITS[unit])
+}
+
+/// The marker for the elided entries of an array or object, like `… 49,990 more items`
+pub(crate) fn more_items(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let noun = if count == 1 { "item" } else { "items" };
+    format!("… {grouped} more {noun}")
+}
@@ -0,0 +1,206 @@
+use crate::highlight::{Highlight, Highlighter};
+use crate::{ColorMode, ColoredFormatter};
+use ratatui::style::{Color as TuiColor, Modifier, Style as TuiStyle};
+use ratatui::text::{Line, Span, Text};
+use serde::Serialize;
+use serde_json::ser::Formatter;
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use yansi::{Color, Style};
+
+impl<F, H> ColoredFormatter<F, H>
+where
+    F: Formatter,
+    H: Highlighter,
+{
+    /// Serialize a value into `ratatui` text, with the styles of the styler
+    ///
+    /// The text is always styled, and colors are not downgraded. Each line of the output becomes
+    /// a [`Line`], so the text can be shown in a scrolling `Paragraph`.
+    ///
+    /// ```rust
+    /// # use colored_json::{ColoredFormatter, PrettyFormatter};
+    /// # use serde_json::json;
+    /// let text = ColoredFormatter::new(PrettyFormatter::new()).to_text(&json!({"a": 1}))?;
+    /// assert_eq!(text.lines.len(), 3);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn to_text<T>(self, value: &T) -> serde_json::Result<Text<'static>>
+    where
+        T: Serialize,
+    {
+        let (highlighter, mut writer) = TextHighlighter::new();
+        self.with_highlighter(highlighter)
+            .write_colored_json(value, &mut writer, ColorMode::On)?;
+        Ok(writer.finish())
+    }
+
+    /// Colorize JSON text into `ratatui` text, see [`ColoredFormatter::write_colored_str`]
+    pub fn str_to_text(self, json: &str) -> serde_json::Result<Text<'static>> {
+        let (highlighter, mut writer) = TextHighlighter::new();
+        self.with_highlighter(highlighter)
+            .write_colored_str(json, &mut writer, ColorMode::On)?;
+        Ok(writer.finish())
+    }
+}
+
+/// Convert a style into the style of `ratatui`
+///
+/// ```rust
+/// # use colored_json::{to_ratatui_style, Color, Style};
+/// use ratatui::style::{Color as TuiColor, Modifier, Style as TuiStyle};
+///
+/// assert_eq!(
+///     to_ratatui_style(Style::new(Color::Blue).bold()),
+///     TuiStyle::new().fg(TuiColor::Blue).add_modifier(Modifier::BOLD)
+/// );
+/// ```
+pub fn to_ratatui_style(style: Style) -> TuiStyle {
+    let mut result = TuiStyle::new();
+    if let Some(color) = to_ratatui_color(style.fg_color()) {
+        result = result.fg(color);
+    }
+    if let Some(color) = to_ratatui_color(style.bg_color()) {
+        result = result.bg(color);
+    }
+
+    let modifiers = [
+        (style.is_bold(), Modifier::BOLD),
+        (style.is_dimmed(), Modifier::DIM),
+        (style.is_italic(), Modifier::ITALIC),
+        (style.is_underline(), Modifier::UNDERLINED),
+        (style.is_blink(), Modifier::SLOW_BLINK),
+        (style.is_invert(), Modifier::REVERSED),
+        (style.is_hidden(), Modifier::HIDDEN),
+        (style.is_strikethrough(), Modifier::CROSSED_OUT),
+    ];
+    for (set, modifier) in modifiers {
+        if set {
+            result = result.add_modifier(modifier);
+        }
+    }
+    result
+}
+
+fn to_ratatui_color(color: Color) -> Option<TuiColor> {
+    Some(match color {
+        Color::Unset => return None,
+        Color::Default => TuiColor::Reset,
+        Color::Black => TuiColor::Black,
+        Color::Red => TuiColor::Red,
+        Color::Green => TuiColor::Green,
+        Color::Yellow => TuiColor::Yellow,
+        Color::Blue => TuiColor::Blue,
+        Color::Magenta => TuiColor::Magenta,
+        Color::Cyan => TuiColor::Cyan,
+        // the white of the basic colors is the gray of `ratatui`
+        Color::White => TuiColor::Gray,
+        Color::Fixed(n) => TuiColor::Indexed(n),
+        Color::RGB(r, g, b) => TuiColor::Rgb(r, g, b),
+    })
+}
+
+/// The text being built, shared by the highlighter and the writer
+#[derive(Default)]
+struct TextState {
+    lines: Vec<Line<'static>>,
+    line: Vec<Span<'static>>,
+    /// the style of the current token
+    style: TuiStyle,
+    /// nothing but indentation was written to the current line
+    indenting: bool,
+}
+
+/// Sets the style of the text written for a token
+struct TextHighlighter {
+    state: Rc<RefCell<TextState>>,
+}
+
+impl TextHighlighter {
+    /// A highlighter, and the writer it styles
+    fn new() -> (Self, TextWriter) {
+        let state = Rc::new(RefCell::new(TextState {
+            indenting: true,
+            ..Default::default()
+        }));
+        let writer = TextWriter {
+            state: state.clone(),
+        };
+        (TextHighlighter { state }, writer)
+    }
+}
+
+impl Highlighter for TextHighlighter {
+    fn begin_token<W>(&mut self, _writer: &mut W, token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.state.borrow_mut().style = to_ratatui_style(token.style);
+        Ok(())
+    }
+
+    fn end_token<W>(&mut self, _writer: &mut W, _token: Highlight) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.state.borrow_mut().style = TuiStyle::new();
+        Ok(())
+    }
+}
+
+/// Splits the output into lines of spans
+struct TextWriter {
+    state: Rc<RefCell<TextState>>,
+}
+
+impl TextWriter {
+    fn finish(self) -> Text<'static> {
+        let mut state = self.state.take();
+        state.lines.push(Line::from(state.line));
+        Text::from(state.lines)
+    }
+}
+
+impl io::Write for TextWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.borrow_mut();
+        let TextState {
+            lines,
+            line,
+            style,
+            indenting,
+        } = &mut *state;
+
+        for (i, mut part) in String::from_utf8_lossy(buf).split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::from(std::mem::take(line)));
+                *indenting = true;
+            }
+            // formatters may write the indentation of closing brackets as part of them
+            if *indenting {
+                let content = part.trim_start_matches([' ', '\t']);
+                push(line, &part[..part.len() - content.len()], TuiStyle::new());
+                part = content;
+                *indenting = part.is_empty();
+            }
+            push(line, part, *style);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Add text to a line, joining it with the last span of the same style
+fn push(line: &mut Vec<Span<'static>>, text: &str, style: TuiStyle) {
+    if text.is_empty() {
+        return;
+    }
+    match line.last_mut() {
+        Some(span) if span.style == style => span.content.to_mut().push_str(text),
+        _ => line.push(Span::styled(text.to_string(), style)),
+    }
+}
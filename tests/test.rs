@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant, clippy::needless_return)]
+
 use colored_json::*;
 use serde_json::json;
 use std::error::Error;
@@ -324,3 +326,1526 @@ fn test_serializes() -> Result<(), Box<dyn Error>> {
 
     return Ok(());
 }
+
+struct TestEnvironment {
+    vars: &'static [(&'static str, &'static str)],
+    terminal: bool,
+}
+
+impl Environment for TestEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v.to_string())
+    }
+
+    fn is_terminal(&self, _output: Output) -> bool {
+        self.terminal
+    }
+}
+
+#[test]
+fn test_color_mode_environment() {
+    let auto = ColorMode::Auto(Output::StdOut);
+    let env = |vars, terminal| TestEnvironment { vars, terminal };
+
+    assert!(auto.use_color_with(&env(&[], true)));
+    assert!(!auto.use_color_with(&env(&[], false)));
+
+    assert!(!auto.use_color_with(&env(&[("NO_COLOR", "1")], true)));
+    assert!(auto.use_color_with(&env(&[("NO_COLOR", "")], true)));
+    assert!(!auto.use_color_with(&env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")], true)));
+
+    assert!(auto.use_color_with(&env(&[("CLICOLOR_FORCE", "1")], false)));
+    assert!(!auto.use_color_with(&env(&[("CLICOLOR_FORCE", "0")], false)));
+    assert!(auto.use_color_with(&env(&[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")], false)));
+
+    assert!(!auto.use_color_with(&env(&[("CLICOLOR", "0")], true)));
+    assert!(auto.use_color_with(&env(&[("CLICOLOR", "1")], true)));
+    assert!(!auto.use_color_with(&env(&[("TERM", "dumb")], true)));
+    assert!(auto.use_color_with(&env(&[("TERM", "xterm-256color")], true)));
+
+    assert!(ColorMode::On.use_color_with(&env(&[("NO_COLOR", "1")], false)));
+    assert!(!ColorMode::Off.use_color_with(&env(&[("CLICOLOR_FORCE", "1")], true)));
+}
+
+#[test]
+fn test_colored_output() -> Result<(), Box<dyn Error>> {
+    let f = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler {
+            key: Style::new(Color::Green),
+            string_value: Style::new(Color::Blue).bold(),
+            integer_value: Style::new(Color::Magenta),
+            ..Default::default()
+        },
+    );
+
+    let s = f.to_colored_json(&json!({"name": "John", "age": [31]}), ColorMode::On)?;
+
+    assert_eq!(
+        s,
+        "\u{1b}[1m{\u{1b}[0m\
+         \u{1b}[32m\"\u{1b}[0m\u{1b}[32mage\u{1b}[0m\u{1b}[32m\"\u{1b}[0m:\
+         \u{1b}[1m[\u{1b}[0m\u{1b}[35m31\u{1b}[0m\u{1b}[1m]\u{1b}[0m,\
+         \u{1b}[32m\"\u{1b}[0m\u{1b}[32mname\u{1b}[0m\u{1b}[32m\"\u{1b}[0m:\
+         \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mJohn\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m\
+         \u{1b}[1m}\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_color_depth_detection() {
+    let env = |vars| TestEnvironment {
+        vars,
+        terminal: true,
+    };
+
+    assert_eq!(
+        ColorDepth::detect_with(&env(&[("COLORTERM", "truecolor"), ("TERM", "xterm")])),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::detect_with(&env(&[("COLORTERM", "24bit")])),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::detect_with(&env(&[("TERM", "xterm-direct")])),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::detect_with(&env(&[("TERM", "xterm-256color")])),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        ColorDepth::detect_with(&env(&[("TERM", "linux")])),
+        ColorDepth::Ansi16
+    );
+}
+
+#[test]
+fn test_color_depth_downgrade() {
+    assert_eq!(
+        ColorDepth::Ansi256.downgrade(Color::RGB(0, 0, 0)),
+        Color::Fixed(16)
+    );
+    assert_eq!(
+        ColorDepth::Ansi256.downgrade(Color::RGB(128, 128, 128)),
+        Color::Fixed(244)
+    );
+    assert_eq!(
+        ColorDepth::Ansi256.downgrade(Color::RGB(95, 135, 175)),
+        Color::Fixed(67)
+    );
+    assert_eq!(ColorDepth::Ansi256.downgrade(Color::Blue), Color::Blue);
+
+    assert_eq!(ColorDepth::Ansi16.downgrade(Color::Fixed(46)), Color::Green);
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::RGB(20, 20, 200)),
+        Color::Blue
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::RGB(240, 240, 240)),
+        Color::White
+    );
+
+    assert_eq!(
+        ColorDepth::TrueColor.downgrade(Color::Fixed(46)),
+        Color::Fixed(46)
+    );
+
+    // grays stay visible, instead of turning black
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::Fixed(8)),
+        Color::Fixed(8)
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::Fixed(13)),
+        Color::Fixed(13)
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::Fixed(244)),
+        Color::Fixed(8)
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::RGB(110, 110, 110)),
+        Color::Fixed(8)
+    );
+    assert_eq!(
+        ColorDepth::Ansi16.downgrade(Color::RGB(20, 20, 20)),
+        Color::Black
+    );
+}
+
+#[test]
+fn test_styler_color_depth() -> Result<(), Box<dyn Error>> {
+    let styler = Styler {
+        key: Style::new(Color::RGB(255, 0, 0)),
+        ..Default::default()
+    };
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_color_depth(ColorDepth::Ansi256)
+        .to_colored_json(&json!({"a": null}), ColorMode::On)?;
+    assert!(s.contains("\u{1b}[38;5;196ma\u{1b}[0m"), "{:?}", s);
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_color_depth(ColorDepth::Ansi16)
+        .to_colored_json(&json!({"a": null}), ColorMode::On)?;
+    assert!(s.contains("\u{1b}[31ma\u{1b}[0m"), "{:?}", s);
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_color_depth(ColorDepth::TrueColor)
+        .to_colored_json(&json!({"a": null}), ColorMode::On)?;
+    assert!(s.contains("\u{1b}[38;2;255;0;0ma\u{1b}[0m"), "{:?}", s);
+
+    // the color mode still decides if color is used at all
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_color_depth(ColorDepth::Ansi256)
+        .to_colored_json(&json!({"a": null}), ColorMode::Off)?;
+    assert_eq!(s, r#"{"a":null}"#);
+
+    Ok(())
+}
+
+#[test]
+fn test_themes() -> Result<(), Box<dyn Error>> {
+    let names: Vec<_> = Theme::names().collect();
+    assert_eq!(names[0], "jq");
+    assert!(names.contains(&"solarized-dark"));
+    assert!(names.contains(&"solarized-light"));
+    assert!(names.contains(&"colorblind"));
+
+    assert_eq!(Styler::from_theme("jq"), Some(Styler::default()));
+    assert_eq!(Styler::from_theme("no-such-theme"), None);
+
+    // all other themes have diff styles of their own
+    let default = Styler::default();
+    for theme in Theme::all().iter().skip(1) {
+        let styler = theme.styler();
+        assert_ne!(styler.added, default.added, "{}", theme.name());
+        assert_ne!(styler.removed, default.removed, "{}", theme.name());
+        assert_ne!(styler.changed, default.changed, "{}", theme.name());
+    }
+
+    for theme in Theme::all() {
+        let styler = Styler::from_theme(theme.name()).expect("theme must exist");
+        assert_eq!(styler, theme.styler());
+        println!(
+            "{} ({:?}): {}\n{}",
+            theme.name(),
+            theme.background(),
+            theme.description(),
+            ColoredFormatter::with_styler(PrettyFormatter::new(), styler).to_colored_json(
+                &json!({"string": "value", "integer": 42, "float": 1.5, "bool": true, "null": null, "array": [1, 2]}),
+                ColorMode::default().eval()
+            )?
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_jq_colors() {
+    let styler = Styler::from_jq_colors("1;30:0;31:0;32:38;5;208:0;32:1;39:1;39:34;1").unwrap();
+    assert_eq!(styler.nil_value, Style::new(Color::Black).bold());
+    assert_eq!(styler.bool_style(false), Style::new(Color::Red));
+    assert_eq!(styler.bool_style(true), Style::new(Color::Green));
+    assert_eq!(styler.integer_value, Style::new(Color::Fixed(208)));
+    assert_eq!(styler.float_value, Style::new(Color::Fixed(208)));
+    assert_eq!(styler.string_value, Style::new(Color::Green));
+    assert_eq!(styler.array_brackets, Style::new(Color::Default).bold());
+    assert_eq!(styler.key, Style::new(Color::Blue).bold());
+
+    // missing fields keep the default
+    let styler = Styler::from_jq_colors("38;2;1;2;3").unwrap();
+    assert_eq!(styler.nil_value, Style::new(Color::RGB(1, 2, 3)));
+    assert_eq!(styler.key, Styler::default().key);
+
+    assert_eq!(
+        Styler::from_jq_colors("0:0:0:0:0;4x")
+            .unwrap_err()
+            .to_string(),
+        "invalid style for 'strings' ('0;4x'): invalid SGR code '4x'"
+    );
+    assert_eq!(
+        Styler::from_jq_colors("0:0:0:0:0:0:0:0:0"),
+        Err(StyleError::TooManyFields(9))
+    );
+    assert!(Styler::from_jq_colors("38;5").is_err());
+    assert!(Styler::from_jq_colors("38;2;1;2").is_err());
+    assert!(Styler::from_jq_colors("60").is_err());
+}
+
+#[test]
+fn test_parse_style() {
+    assert_eq!(parse_style("").unwrap(), Style::default());
+    assert_eq!(
+        parse_style("Bold underline bright-red on blue").unwrap(),
+        Style::new(Color::Fixed(9))
+            .bg(Color::Blue)
+            .bold()
+            .underline()
+    );
+    assert_eq!(
+        parse_style("#00ff00").unwrap(),
+        Style::new(Color::RGB(0, 255, 0))
+    );
+
+    assert_eq!(
+        parse_style("blue bolt").unwrap_err().to_string(),
+        "invalid style 'blue bolt': unknown attribute or more than one foreground color: 'bolt'"
+    );
+    assert!(parse_style("#12345").is_err());
+    assert!(parse_style("red on").is_err());
+    assert!(parse_style("on red on blue").is_err());
+    assert!(parse_style("256").is_err());
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_styler_config() {
+    let styler: Styler = serde_json::from_value(json!({
+        "key": "blue bold",
+        "nil_value": "red on white",
+        "true_value": "green",
+        "string_include_quotation": false,
+    }))
+    .unwrap();
+    assert_eq!(styler.true_value, Some(Style::new(Color::Green)));
+    assert_eq!(styler.false_value, None);
+    assert_eq!(styler.key, Style::new(Color::Blue).bold());
+    assert_eq!(styler.nil_value, Style::new(Color::Red).bg(Color::White));
+    assert_eq!(styler.string_value, Styler::default().string_value);
+    assert!(!styler.string_include_quotation);
+
+    let styler: Styler = serde_json::from_value(json!({
+        "theme": "solarized-dark",
+        "key": "green",
+    }))
+    .unwrap();
+    assert_eq!(styler.key, Style::new(Color::Green));
+    assert_eq!(
+        styler.string_value,
+        Styler::from_theme("solarized-dark").unwrap().string_value
+    );
+
+    let err = serde_json::from_value::<Styler>(json!({"key": "blue bolt"})).unwrap_err();
+    assert!(
+        err.to_string().starts_with("invalid style for 'key'"),
+        "{}",
+        err
+    );
+    let err = serde_json::from_value::<Styler>(json!({"theme": "nope"})).unwrap_err();
+    assert!(
+        err.to_string().starts_with("unknown theme 'nope'"),
+        "{}",
+        err
+    );
+    assert!(serde_json::from_value::<Styler>(json!({"keys": "blue"})).is_err());
+}
+
+#[test]
+fn test_bool_styles() -> Result<(), Box<dyn Error>> {
+    let styler = Styler {
+        bool_value: Style::new(Color::Yellow),
+        ..Default::default()
+    };
+    assert_eq!(styler.bool_style(true), Style::new(Color::Yellow));
+    assert_eq!(styler.bool_style(false), Style::new(Color::Yellow));
+
+    let styler = styler.with_true_value(Style::new(Color::Green));
+    assert_eq!(styler.bool_style(true), Style::new(Color::Green));
+    assert_eq!(styler.bool_style(false), Style::new(Color::Yellow));
+
+    let styler = styler.with_false_value(Style::new(Color::Red));
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .to_colored_json(&json!([true, false]), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m[\u{1b}[0m\u{1b}[32mtrue\u{1b}[0m,\u{1b}[31mfalse\u{1b}[0m\u{1b}[1m]\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_escape_style() -> Result<(), Box<dyn Error>> {
+    let styler = Styler {
+        key: Style::new(Color::Blue),
+        string_value: Style::new(Color::Green),
+        string_include_quotation: false,
+        ..Default::default()
+    };
+
+    // without an escape style, escapes get the style of the string
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .to_colored_json(&json!({"a\tb": "c\nd"}), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m{\u{1b}[0m\"\u{1b}[34ma\u{1b}[0m\u{1b}[34m\\t\u{1b}[0m\u{1b}[34mb\u{1b}[0m\":\
+         \"\u{1b}[32mc\u{1b}[0m\u{1b}[32m\\n\u{1b}[0m\u{1b}[32md\u{1b}[0m\"\u{1b}[1m}\u{1b}[0m"
+    );
+
+    let styler = styler.with_escape(Style::new(Color::Red));
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .to_colored_json(&json!({"a\tb": "\"\u{0}"}), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m{\u{1b}[0m\"\u{1b}[34ma\u{1b}[0m\u{1b}[31m\\t\u{1b}[0m\u{1b}[34mb\u{1b}[0m\":\
+         \"\u{1b}[31m\\\"\u{1b}[0m\u{1b}[31m\\u0000\u{1b}[0m\"\u{1b}[1m}\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_value() -> Result<(), Box<dyn Error>> {
+    #[derive(serde::Serialize)]
+    struct Data<'a> {
+        raw: &'a serde_json::value::RawValue,
+    }
+
+    let raw = serde_json::value::RawValue::from_string(
+        r#"{"a": [1, 2.5, true, null], "b\n": "c\"d"}"#.to_string(),
+    )?;
+    let styler = Styler {
+        key: Style::new(Color::Blue),
+        string_value: Style::new(Color::Green),
+        integer_value: Style::new(Color::Cyan),
+        float_value: Style::new(Color::Magenta),
+        nil_value: Style::new(Color::Black),
+        string_include_quotation: false,
+        ..Default::default()
+    }
+    .with_true_value(Style::new(Color::Yellow))
+    .with_escape(Style::new(Color::Red));
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .to_colored_json(&Data { raw: &raw }, ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m{\u{1b}[0m\"\u{1b}[34mraw\u{1b}[0m\":\
+         \u{1b}[1m{\u{1b}[0m\"\u{1b}[34ma\u{1b}[0m\": \u{1b}[1m[\u{1b}[0m\u{1b}[36m1\u{1b}[0m, \
+         \u{1b}[35m2.5\u{1b}[0m, \u{1b}[33mtrue\u{1b}[0m, \u{1b}[30mnull\u{1b}[0m\u{1b}[1m]\u{1b}[0m, \
+         \"\u{1b}[34mb\u{1b}[0m\u{1b}[31m\\n\u{1b}[0m\": \
+         \"\u{1b}[32mc\u{1b}[0m\u{1b}[31m\\\"\u{1b}[0m\u{1b}[32md\u{1b}[0m\"\u{1b}[1m}\u{1b}[0m\
+         \u{1b}[1m}\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_number_str() -> Result<(), Box<dyn Error>> {
+    use serde_json::ser::Formatter;
+
+    let mut f = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler {
+            integer_value: Style::new(Color::Cyan),
+            float_value: Style::new(Color::Magenta),
+            ..Default::default()
+        },
+    );
+
+    let mut out = Vec::new();
+    f.write_number_str(&mut out, "123456789012345678901234567890")?;
+    f.write_number_str(&mut out, "1.0e3")?;
+    f.write_number_str(&mut out, "-2E-7")?;
+    f.write_number_str(&mut out, "3.14")?;
+    assert_eq!(
+        String::from_utf8(out)?,
+        "\u{1b}[36m123456789012345678901234567890\u{1b}[0m\
+         \u{1b}[35m1.0e3\u{1b}[0m\u{1b}[35m-2E-7\u{1b}[0m\u{1b}[35m3.14\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_rainbow_brackets() -> Result<(), Box<dyn Error>> {
+    let styler = Styler {
+        key: Style::default(),
+        string_value: Style::default(),
+        ..Default::default()
+    };
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_rainbow_brackets(vec![Style::new(Color::Red), Style::new(Color::Green)])
+        .to_colored_json(&json!([{"a": [[]]}, []]), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[31m[\u{1b}[0m\
+         \u{1b}[32m{\u{1b}[0m\"a\":\
+         \u{1b}[31m[\u{1b}[0m\u{1b}[32m[\u{1b}[0m\u{1b}[32m]\u{1b}[0m\u{1b}[31m]\u{1b}[0m\
+         \u{1b}[32m}\u{1b}[0m,\
+         \u{1b}[32m[\u{1b}[0m\u{1b}[32m]\u{1b}[0m\
+         \u{1b}[31m]\u{1b}[0m"
+    );
+
+    println!(
+        "{}",
+        ColoredFormatter::new(PrettyFormatter::new())
+            .with_rainbow_brackets(Styler::rainbow_palette())
+            .to_colored_json(
+                &json!({"a": {"b": {"c": {"d": {"e": {"f": {"g": [1, 2]}}}}}}}),
+                ColorMode::default().eval()
+            )?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_path_rules() -> Result<(), Box<dyn Error>> {
+    let plain = Styler {
+        object_brackets: Style::default(),
+        object_colon: Style::default(),
+        array_brackets: Style::default(),
+        key: Style::default(),
+        string_value: Style::default(),
+        integer_value: Style::default(),
+        float_value: Style::default(),
+        bool_value: Style::default(),
+        nil_value: Style::default(),
+        ..Default::default()
+    };
+    let red = Style::new(Color::Red);
+    let green = Style::new(Color::Green);
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, plain)
+        .with_path_rule(PathRule::key("error").style(red))
+        .to_colored_json(&json!({"error": {"code": 1}, "ok": true}), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "{\u{1b}[31m\"\u{1b}[0m\u{1b}[31merror\u{1b}[0m\u{1b}[31m\"\u{1b}[0m:\
+         \u{1b}[31m{\u{1b}[0m\
+         \u{1b}[31m\"\u{1b}[0m\u{1b}[31mcode\u{1b}[0m\u{1b}[31m\"\u{1b}[0m\u{1b}[31m:\u{1b}[0m\
+         \u{1b}[31m1\u{1b}[0m\
+         \u{1b}[31m}\u{1b}[0m,\
+         \"ok\":true}"
+    );
+
+    // the key style only applies to the key, a later rule takes precedence
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, plain)
+        .with_path_rule(PathRule::pointer("/items/*/id").value_style(red))
+        .with_path_rule(PathRule::pointer("/items/1/id").value_style(green))
+        .with_path_rule(PathRule::pointer("/**/a\"b").key_style(green))
+        .to_colored_json(
+            &json!({"items": [{"id": 1}, {"id": 2, "a\"b": 3}]}),
+            ColorMode::On,
+        )?;
+    assert_eq!(
+        s,
+        "{\"items\":[{\"id\":\u{1b}[31m1\u{1b}[0m},\
+         {\u{1b}[32m\"\u{1b}[0m\u{1b}[32ma\u{1b}[0m\u{1b}[32m\\\"\u{1b}[0m\u{1b}[32mb\u{1b}[0m\u{1b}[32m\"\u{1b}[0m:3,\
+         \"id\":\u{1b}[32m2\u{1b}[0m}]}"
+    );
+
+    // non-string keys are matched by their string representation
+    let map: std::collections::BTreeMap<u8, u8> = [(1, 2), (3, 4)].into();
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, plain)
+        .with_path_rule(PathRule::key("3").key_style(red))
+        .to_colored_json(&map, ColorMode::On)?;
+    assert_eq!(
+        s,
+        "{\"1\":2,\u{1b}[31m\"\u{1b}[0m\u{1b}[31m3\u{1b}[0m\u{1b}[31m\"\u{1b}[0m:4}"
+    );
+
+    #[cfg(feature = "regex")]
+    {
+        let s = ColoredFormatter::with_styler(CompactFormatter {}, plain)
+            .with_path_rule(PathRule::regex(regex::Regex::new("^/a/[0-9]+$")?).value_style(red))
+            .to_colored_json(&json!({"a": [1, [2]], "b": 3}), ColorMode::On)?;
+        assert_eq!(
+            s,
+            "{\"a\":[\u{1b}[31m1\u{1b}[0m,\
+             \u{1b}[31m[\u{1b}[0m\u{1b}[31m2\u{1b}[0m\u{1b}[31m]\u{1b}[0m],\"b\":3}"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_cli() -> Result<(), Box<dyn Error>> {
+    use std::process::{Command, Stdio};
+
+    let run = |args: &[&str], input: &str| -> Result<_, Box<dyn Error>> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_colored_json"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or("no stdin")?
+            .write_all(input.as_bytes())?;
+        Ok(child.wait_with_output()?)
+    };
+
+    let output = run(
+        &["--color=never", "--compact"],
+        r#"{"b": 1, "a": [true]} 2"#,
+    )?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "{\"b\":1,\"a\":[true]}\n2\n"
+    );
+
+    let output = run(
+        &["--color=never", "--indent=4", "--sort-keys"],
+        r#"{"b": 1, "a": {"d": 2, "c": 3}}"#,
+    )?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "{\n    \"a\": {\n        \"c\": 3,\n        \"d\": 2\n    },\n    \"b\": 1\n}\n"
+    );
+
+    let output = run(
+        &["--color=always", "--compact", "--theme=high-contrast"],
+        "null",
+    )?;
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "\u{1b}[1;31mnull\u{1b}[0m\n"
+    );
+
+    let output = run(&[], r#"{"a": }"#)?;
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr)?,
+        "error: expected value\n --> <stdin>:1:7\n  |\n1 | {\"a\": }\n  |       ^\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stream() -> Result<(), Box<dyn Error>> {
+    let input = "{\"a\": 1}\n\
+                 \n\
+                 [1, 2] \"x\"\n\
+                 {\"b\": tru}\n\
+                 {\"c\":\n\
+                 {\"d\": [\n  \
+                   true\n\
+                 ]}\n\
+                 {\"e\": [";
+
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    StreamColorizer::new(ColoredFormatter::new(CompactFormatter {}))
+        .with_mode(ColorMode::Off)
+        .colorize(input.as_bytes(), &mut output, |err| errors.push(err))?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "{\"a\":1}\n[1,2]\n\"x\"\n{\"d\":[true]}\n"
+    );
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|err| (err.line(), err.text().to_string(), err.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                4,
+                "{\"b\": tru}".to_string(),
+                "expected ident at line 4 column 10".to_string()
+            ),
+            (
+                6,
+                "{\"c\":".to_string(),
+                "EOF while parsing a value at line 6 column 0".to_string()
+            ),
+            (
+                9,
+                "{\"e\": [".to_string(),
+                "EOF while parsing a list at line 9 column 7".to_string()
+            ),
+        ]
+    );
+
+    let mut output = Vec::new();
+    StreamColorizer::new(ColoredFormatter::new(PrettyFormatter::new()))
+        .with_mode(ColorMode::Off)
+        .with_echo_invalid(true)
+        .colorize("1 {\n2".as_bytes(), &mut output, |_| {})?;
+    assert_eq!(String::from_utf8(output)?, "1\n{\n2\n");
+
+    // the values are colorized as text, keeping the order of keys and the numbers
+    let mut output = Vec::new();
+    StreamColorizer::new(ColoredFormatter::new(CompactFormatter {}))
+        .with_mode(ColorMode::Off)
+        .colorize(
+            "{\"z\": 123456789012345678901234567890, \"a\": 1.50}\n".as_bytes(),
+            &mut output,
+            |_| {},
+        )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "{\"z\":123456789012345678901234567890,\"a\":1.50}\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_colored_str() -> Result<(), Box<dyn Error>> {
+    let json = r#"{"b": [1.0e3, -0, "a\u00e9\n"], "a": {}, "b": true}"#;
+
+    let s = json.to_colored_json(ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "{\n  \"b\": [\n    1.0e3,\n    -0,\n    \"a\\u00e9\\n\"\n  ],\n  \"a\": {},\n  \"b\": true\n}"
+    );
+
+    // the same output as serializing a value, if there is nothing to preserve
+    let value = json!({"a": [1, {"b": null, "c": [[], "x"]}], "d": 2.5});
+    let styler = Styler {
+        escape: Some(Style::new(Color::Red)),
+        ..Default::default()
+    };
+    let f = ColoredFormatter::with_styler(PrettyFormatter::new(), styler)
+        .with_rainbow_brackets(Styler::rainbow_palette())
+        .with_color_depth(ColorDepth::TrueColor);
+    assert_eq!(
+        f.clone()
+            .to_colored_str(&serde_json::to_string(&value)?, ColorMode::On)?,
+        f.to_colored_json(&value, ColorMode::On)?
+    );
+
+    // keeping the whitespace
+    let s = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler {
+            key: Style::new(Color::Green),
+            ..Default::default()
+        },
+    )
+    .with_original_whitespace(true)
+    .to_colored_str("{ \"a\" :\n 1 }", ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m{\u{1b}[0m \u{1b}[32m\"\u{1b}[0m\u{1b}[32ma\u{1b}[0m\u{1b}[32m\"\u{1b}[0m :\n 1 \u{1b}[1m}\u{1b}[0m"
+    );
+
+    // path rules apply to keys of the text
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_path_rule(PathRule::pointer("/x/1").value_style(Style::new(Color::Red)))
+        .to_colored_str(r#"{"x": [1, 2]}"#, ColorMode::On)?;
+    assert!(s.contains("\u{1b}[31m2\u{1b}[0m"));
+
+    assert!(ColoredFormatter::new(CompactFormatter {})
+        .to_colored_str(r#"{"a": 1,}"#, ColorMode::On)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_lenient() -> Result<(), Box<dyn Error>> {
+    let f = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler {
+            key: Style::new(Color::Green),
+            object_brackets: Style::default(),
+            array_brackets: Style::default(),
+            ..Default::default()
+        }
+        .with_error(Style::new(Color::Red).underline()),
+    );
+
+    // valid input is written as usual
+    let (s, error) = f.clone().to_colored_str_lenient("[1, 2]", ColorMode::Off);
+    assert_eq!(s, "[1,2]");
+    assert!(error.is_none());
+
+    let (s, error) = f.clone().to_colored_str_lenient("[1,\n x]", ColorMode::On);
+    assert_eq!(s, "[1,\n \u{1b}[4;31mx\u{1b}[0m]");
+    let error = error.ok_or("invalid input")?;
+    assert_eq!((error.offset(), error.line(), error.column()), (5, 2, 2));
+    assert_eq!(error.to_string(), "expected value at line 2 column 2");
+
+    // truncated input
+    let (s, error) = f
+        .clone()
+        .to_colored_str_lenient("{\"a\": \"b", ColorMode::On);
+    assert_eq!(
+        s,
+        "{\u{1b}[32m\"\u{1b}[0m\u{1b}[32ma\u{1b}[0m\u{1b}[32m\"\u{1b}[0m: \
+         \u{1b}[4;31m\"b\u{1b}[0m"
+    );
+    let error = error.ok_or("truncated input")?;
+    assert_eq!(error.offset(), 8);
+    assert!(error.error().is_eof());
+
+    // truncated input, which ends with a valid token
+    let (s, error) = f.to_colored_str_lenient("{\"a\": [1,\n", ColorMode::On);
+    assert_eq!(
+        s,
+        "{\u{1b}[32m\"\u{1b}[0m\u{1b}[32ma\u{1b}[0m\u{1b}[32m\"\u{1b}[0m: [1\u{1b}[4;31m,\u{1b}[0m\n"
+    );
+    let error = error.ok_or("truncated input")?;
+    assert!(error.error().is_eof());
+
+    Ok(())
+}
+
+#[test]
+fn test_syntax_error_report() -> Result<(), Box<dyn Error>> {
+    let json = "[\n  1,\n  2,\n  3,\n  x\n]";
+    let error = match json.to_colored_json(ColorMode::Off) {
+        Ok(_) => return Err("invalid JSON must fail".into()),
+        Err(err) => SyntaxError::new(json, err),
+    };
+    assert_eq!((error.line(), error.column(), error.offset()), (5, 3, 19));
+
+    assert_eq!(
+        error
+            .report(ColorMode::On)
+            .with_name("data.json")
+            .to_string(),
+        "\u{1b}[1;31merror:\u{1b}[0m \u{1b}[1mexpected value\u{1b}[0m\n \
+         \u{1b}[1;34m-->\u{1b}[0m data.json:5:3\n  \
+         \u{1b}[1;34m|\u{1b}[0m\n\
+         \u{1b}[1;34m3 |\u{1b}[0m   2,\n\
+         \u{1b}[1;34m4 |\u{1b}[0m   3,\n\
+         \u{1b}[1;34m5 |\u{1b}[0m   x\n  \
+         \u{1b}[1;34m|\u{1b}[0m   \u{1b}[1;31m^\u{1b}[0m\n"
+    );
+
+    // errors without a location, like I/O errors, have no snippet
+    let error = SyntaxError::new("", serde_json::Error::io(std::io::ErrorKind::Other.into()));
+    assert_eq!(
+        error.report(ColorMode::Off).to_string(),
+        "error: other error\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_html() -> Result<(), Box<dyn Error>> {
+    let styler = Styler {
+        object_brackets: Style::default(),
+        array_brackets: Style::default(),
+        ..Default::default()
+    }
+    .with_escape(Style::new(Color::Red));
+
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_html(HtmlStyle::Classes)
+        .to_colored_json(&json!({"a&b": ["x\n", true, null]}), ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "<span class=\"json-object-bracket\">{</span>\
+         <span class=\"json-key\">&quot;a&amp;b&quot;</span>\
+         <span class=\"json-colon\">:</span>\
+         <span class=\"json-array-bracket\">[</span>\
+         <span class=\"json-string\">&quot;x<span class=\"json-escape\">\\n</span>&quot;</span>,\
+         <span class=\"json-bool json-true\">true</span>,\
+         <span class=\"json-null\">null</span>\
+         <span class=\"json-array-bracket\">]</span>\
+         <span class=\"json-object-bracket\">}</span>"
+    );
+
+    // only styled tokens get an element, and colors are not downgraded
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_html(HtmlStyle::Inline)
+        .with_color_depth(ColorDepth::Ansi16)
+        .with_path_rule(PathRule::key("b").value_style(Style::new(Color::RGB(1, 2, 3)).bold()))
+        .to_colored_json(&json!({"b": 1, "c": "<'>"}), ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "{<span style=\"color: #0000ee; font-weight: bold;\">&quot;b&quot;</span>:\
+         <span style=\"color: #010203; font-weight: bold;\">1</span>,\
+         <span style=\"color: #0000ee; font-weight: bold;\">&quot;c&quot;</span>:\
+         <span style=\"color: #00cd00;\">&quot;&lt;&#39;&gt;&quot;</span>}"
+    );
+
+    // overrides of path rules are added to the class
+    let s = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_html(HtmlStyle::Classes)
+        .with_path_rule(PathRule::pointer("/0").value_style(Style::default().italic()))
+        .to_colored_json(&json!([1]), ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "<span class=\"json-array-bracket\">[</span>\
+         <span class=\"json-integer\" style=\"font-style: italic;\">1</span>\
+         <span class=\"json-array-bracket\">]</span>"
+    );
+
+    // so are the styles of rainbow brackets, for values and text
+    let f = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_html(HtmlStyle::Classes)
+        .with_rainbow_brackets([Style::new(Color::Red), Style::new(Color::Blue)]);
+    let s = f.clone().to_colored_json(&json!([{}]), ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "<span class=\"json-array-bracket\" style=\"color: #cd0000;\">[</span>\
+         <span class=\"json-object-bracket\" style=\"color: #0000ee;\">{</span>\
+         <span class=\"json-object-bracket\" style=\"color: #0000ee;\">}</span>\
+         <span class=\"json-array-bracket\" style=\"color: #cd0000;\">]</span>"
+    );
+    assert_eq!(s, f.to_colored_str("[{}]", ColorMode::Off)?);
+
+    // lenient highlighting marks invalid input
+    let (s, error) = ColoredFormatter::with_styler(CompactFormatter {}, styler)
+        .with_html(HtmlStyle::Classes)
+        .to_colored_str_lenient("[<]", ColorMode::Off);
+    assert!(error.is_some());
+    assert_eq!(
+        s,
+        "<span class=\"json-array-bracket\">[</span>\
+         <span class=\"json-error\">&lt;</span>\
+         <span class=\"json-array-bracket\">]</span>"
+    );
+
+    assert_eq!(
+        Styler::default().to_css(),
+        ".json-object-bracket { font-weight: bold; }\n\
+         .json-array-bracket { font-weight: bold; }\n\
+         .json-key { color: #0000ee; font-weight: bold; }\n\
+         .json-string { color: #00cd00; }\n\
+         .json-error { color: #e5e5e5; background-color: #cd0000; }\n\
+         .json-elided { opacity: 0.5; }\n\
+         .json-added { color: #00cd00; }\n\
+         .json-removed { color: #cd0000; }\n\
+         .json-changed { color: #cdcd00; }\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_highlighter() -> Result<(), Box<dyn Error>> {
+    /// Writes the role of each token, and marks strings with `<>`
+    #[derive(Clone, Default)]
+    struct Roles {
+        terminal: bool,
+    }
+
+    impl Highlighter for Roles {
+        fn begin_token<W>(&mut self, writer: &mut W, token: Highlight) -> std::io::Result<()>
+        where
+            W: ?Sized + std::io::Write,
+        {
+            write!(
+                writer,
+                "{:?}{}(",
+                token.role,
+                if token.overridden { "!" } else { "" }
+            )
+        }
+
+        fn end_token<W>(&mut self, writer: &mut W, _token: Highlight) -> std::io::Result<()>
+        where
+            W: ?Sized + std::io::Write,
+        {
+            writer.write_all(b")")
+        }
+
+        fn begin_string<W>(&mut self, writer: &mut W, _token: Highlight) -> std::io::Result<()>
+        where
+            W: ?Sized + std::io::Write,
+        {
+            writer.write_all(b"<")
+        }
+
+        fn end_string<W>(&mut self, writer: &mut W, _token: Highlight) -> std::io::Result<()>
+        where
+            W: ?Sized + std::io::Write,
+        {
+            writer.write_all(b">")
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.terminal
+        }
+    }
+
+    let f = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler::default().with_escape(Style::new(Color::Red)),
+    )
+    .with_path_rule(PathRule::key("b").value_style(Style::new(Color::Cyan)));
+    let value = json!({"a": ["x\t", 1.5, false], "b": null});
+
+    // other than terminals, highlighting doesn't depend on the color mode
+    let s = f
+        .clone()
+        .with_highlighter(Roles::default())
+        .to_colored_json(&value, ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "ObjectBracket({)<Key(\")Key(a)Key(\")>Colon(:)ArrayBracket([)\
+         <String(\")String(x)Escape(\\t)String(\")>,Float(1.5),False(false)ArrayBracket(])\
+         ,<Key(\")Key(b)Key(\")>Colon(:)Null!(null)ObjectBracket(})"
+    );
+
+    let s = f
+        .with_highlighter(Roles { terminal: true })
+        .to_colored_json(&value, ColorMode::Off)?;
+    assert_eq!(s, r#"{"a":["x\t",1.5,false],"b":null}"#);
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens() -> Result<(), Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    visit_tokens(
+        &json!({"a": ["b", 1, 1.5, true, null], "c": {}}),
+        CompactFormatter {},
+        |kind, text| tokens.push((kind, text.to_string())),
+    )?;
+    let tokens: Vec<_> = tokens.iter().map(|(k, t)| (*k, t.as_str())).collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenKind::BeginObject, "{"),
+            (TokenKind::Key, "\"a\""),
+            (TokenKind::Colon, ":"),
+            (TokenKind::BeginArray, "["),
+            (TokenKind::String, "\"b\""),
+            (TokenKind::Comma, ","),
+            (TokenKind::Integer, "1"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Float, "1.5"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Bool, "true"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Null, "null"),
+            (TokenKind::EndArray, "]"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Key, "\"c\""),
+            (TokenKind::Colon, ":"),
+            (TokenKind::BeginObject, "{"),
+            (TokenKind::EndObject, "}"),
+            (TokenKind::EndObject, "}"),
+        ]
+    );
+
+    // the tokens are the same as lexing the serialized text, with whitespace joined
+    let value = json!({"a": ["b\n", 1], "c": {"d": []}});
+    let mut tokens = Vec::new();
+    visit_tokens(&value, PrettyFormatter::new(), |kind, text| {
+        tokens.push((kind, text.to_string()))
+    })?;
+    let json = serde_json::to_string_pretty(&value)?;
+    let lexed: Vec<_> = Lexer::new(&json).map(|(k, t)| (k, t.to_string())).collect();
+    assert_eq!(tokens, lexed);
+
+    // all of the text is covered, including whitespace and invalid input
+    let json = "[\n  \"a\\\"\", -2e3,\n  nul ]";
+    let tokens: Vec<_> = Lexer::new(json).collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenKind::BeginArray, "["),
+            (TokenKind::Whitespace, "\n  "),
+            (TokenKind::String, "\"a\\\"\""),
+            (TokenKind::Comma, ","),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::Float, "-2e3"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Whitespace, "\n  "),
+            (TokenKind::Error, "nul"),
+            (TokenKind::Whitespace, " "),
+            (TokenKind::EndArray, "]"),
+        ]
+    );
+    assert_eq!(tokens.iter().map(|(_, t)| *t).collect::<String>(), json);
+
+    Ok(())
+}
+
+#[cfg(feature = "ratatui")]
+#[test]
+fn test_ratatui() -> Result<(), Box<dyn Error>> {
+    use ratatui::style::{Color as TuiColor, Modifier, Style as TuiStyle};
+    use ratatui::text::{Line, Span};
+
+    let f = ColoredFormatter::with_styler(
+        PrettyFormatter::new(),
+        Styler {
+            object_brackets: Style::default(),
+            array_brackets: Style::new(Color::Fixed(208)),
+            ..Default::default()
+        },
+    );
+
+    let key = TuiStyle::new()
+        .fg(TuiColor::Blue)
+        .add_modifier(Modifier::BOLD);
+    let string = TuiStyle::new().fg(TuiColor::Green);
+    let bracket = TuiStyle::new().fg(TuiColor::Indexed(208));
+
+    let text = f.clone().to_text(&json!({"a": ["b"], "c": 1}))?;
+    assert_eq!(
+        text.lines,
+        [
+            Line::from("{"),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("\"a\"", key),
+                Span::raw(": "),
+                Span::styled("[", bracket),
+            ]),
+            Line::from(vec![Span::raw("    "), Span::styled("\"b\"", string)]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("]", bracket),
+                Span::raw(",")
+            ]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("\"c\"", key),
+                Span::raw(": 1"),
+            ]),
+            Line::from("}"),
+        ]
+    );
+
+    // JSON text keeps the order of keys
+    let text = f.str_to_text(r#"{"z": "\n", "a": true}"#)?;
+    assert_eq!(
+        text.lines[1],
+        Line::from(vec![
+            Span::raw("  "),
+            Span::styled("\"z\"", key),
+            Span::raw(": "),
+            Span::styled("\"\\n\"", string),
+            Span::raw(","),
+        ])
+    );
+    assert_eq!(text.lines.len(), 4);
+
+    assert!(ColoredFormatter::new(PrettyFormatter::new())
+        .str_to_text("[1,")
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_sort_keys() -> Result<(), Box<dyn Error>> {
+    #[derive(serde::Serialize)]
+    struct Inner {
+        y: u8,
+        x: Vec<f64>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Outer {
+        b: Inner,
+        a: &'static str,
+    }
+
+    let value = Outer {
+        b: Inner { y: 1, x: vec![1.5] },
+        a: "z",
+    };
+
+    // values and text give the same output
+    let f = ColoredFormatter::new(PrettyFormatter::new()).with_sort_keys(true);
+    let s = f.clone().to_colored_json(&value, ColorMode::On)?;
+    assert_eq!(
+        s,
+        f.clone()
+            .to_colored_str(r#"{"b":{"y":1,"x":[1.5]},"a":"z"}"#, ColorMode::On)?
+    );
+    assert_eq!(
+        f.to_colored_json(&value, ColorMode::Off)?,
+        "{\n  \"a\": \"z\",\n  \"b\": {\n    \"x\": [\n      1.5\n    ],\n    \"y\": 1\n  }\n}"
+    );
+
+    // keys are compared unescaped, and the sort is stable
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_sort_keys(true)
+        .to_colored_str(
+            r#"{"b": 1.0e3, "a": [{"d": 1, "c": 2}], "b": 2}"#,
+            ColorMode::Off,
+        )?;
+    assert_eq!(s, r#"{"a":[{"c":2,"d":1}],"b":1.0e3,"b":2}"#);
+
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_sort_keys(true)
+        .to_colored_str(r#"{"\u0063": 0, "b": 1}"#, ColorMode::Off)?;
+    assert_eq!(s, r#"{"b":1,"\u0063":0}"#);
+
+    // non-string keys are compared by their string representation
+    let map: std::collections::HashMap<u16, u8> = [(10, 1), (9, 2), (100, 3)].into();
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_sort_keys(true)
+        .to_colored_json(&map, ColorMode::Off)?;
+    assert_eq!(s, r#"{"10":1,"100":3,"9":2}"#);
+
+    // path rules apply to the sorted entries
+    let red = Style::new(Color::Red);
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_sort_keys(true)
+        .with_path_rule(PathRule::pointer("/a/0").value_style(red))
+        .to_colored_str(r#"{"b": [1], "a": [2]}"#, ColorMode::On)?;
+    assert!(s.contains("\u{1b}[31m2\u{1b}[0m"));
+    assert!(!s.contains("\u{1b}[31m1\u{1b}[0m"));
+
+    Ok(())
+}
+
+#[test]
+fn test_format_options() -> Result<(), Box<dyn Error>> {
+    let value = json!({"a": [1, 2], "b": {"c": null}});
+
+    let options = FormatOptions::default().with_indent(Indent::Tab);
+    let s = to_colored_json_with_options(&value, ColorMode::Off, &options)?;
+    assert_eq!(
+        s,
+        "{\n\t\"a\": [\n\t\t1,\n\t\t2\n\t],\n\t\"b\": {\n\t\t\"c\": null\n\t}\n}"
+    );
+
+    // containers which fit are kept on one line, the others are broken up as far as needed
+    let options = FormatOptions::default()
+        .with_indent(Indent::Spaces(4))
+        .with_max_width(24);
+    let s = to_colored_json_with_options(&value, ColorMode::Off, &options)?;
+    assert_eq!(s, "{\n    \"a\": [1, 2],\n    \"b\": {\"c\": null}\n}");
+
+    let s = to_colored_json_with_options(&json!([[1, 2], []]), ColorMode::Off, &options)?;
+    assert_eq!(s, "[[1, 2], []]");
+
+    let s = r#"[{"long": "abcdefghijklmnopqrstuvwxyz"}, 1.0e3]"#
+        .to_colored_json_with_options(ColorMode::Off, &options)?;
+    assert_eq!(
+        s,
+        "[\n    {\n        \"long\": \"abcdefghijklmnopqrstuvwxyz\"\n    },\n    1.0e3\n]"
+    );
+
+    // the trailing comma counts, and keys get sorted before the layout
+    let options = options.with_max_width(17).with_sort_keys(true);
+    let s = r#"{"z": [10, 20], "a": [1, 2, 3, 4]}"#
+        .to_colored_json_with_options(ColorMode::Off, &options)?;
+    assert_eq!(s, "{\n    \"a\": [\n        1,\n        2,\n        3,\n        4\n    ],\n    \"z\": [10, 20]\n}");
+
+    // the separators of a line are not colored
+    let s = to_colored_json_with_options(&json!([true, null]), ColorMode::On, &options)?;
+    assert_eq!(s, "\u{1b}[1m[\u{1b}[0mtrue, null\u{1b}[1m]\u{1b}[0m");
+
+    assert!("[1,"
+        .to_colored_json_with_options(ColorMode::Off, &options)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_truncate() -> Result<(), Box<dyn Error>> {
+    let value = json!({
+        "blob": "x".repeat(2_000_000),
+        "items": (0..50_000).collect::<Vec<_>>(),
+        "nested": {"deep": {"deeper": [1]}, "empty": {}}
+    });
+
+    let s = ColoredFormatter::new(PrettyFormatter::new())
+        .with_max_string_length(8)
+        .with_max_items(10)
+        .with_max_depth(2)
+        .to_colored_json(&value, ColorMode::Off)?;
+    assert_eq!(
+        s,
+        r#"{
+  "blob": "xxxxxxxx…(2.0 MB elided)",
+  "items": [
+    0,
+    1,
+    2,
+    3,
+    4,
+    5,
+    6,
+    7,
+    8,
+    9,
+    … 49,990 more items
+  ],
+  "nested": {
+    "deep": {…},
+    "empty": {}
+  }
+}"#
+    );
+
+    // escapes count as one character, keys are not truncated
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_max_string_length(2)
+        .to_colored_str(r#"{"long key": "\"ab", "short": "ab"}"#, ColorMode::Off)?;
+    assert_eq!(s, r#"{"long key":"\"a…(1 B elided)","short":"ab"}"#);
+
+    // serialized escapes count with their length in JSON
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_max_string_length(1)
+        .to_colored_json(&json!(["\n\n\u{1}b"]), ColorMode::Off)?;
+    assert_eq!(s, r#"["\n…(9 B elided)"]"#);
+
+    // sorted objects are truncated after sorting
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_sort_keys(true)
+        .with_max_items(2)
+        .to_colored_json(
+            &json!({"c": 1, "b": {"e": 2, "d": 3}, "a": 4}),
+            ColorMode::Off,
+        )?;
+    assert_eq!(s, r#"{"a":4,"b":{"d":3,"e":2},… 1 more item}"#);
+
+    let s = ColoredFormatter::new(CompactFormatter {})
+        .with_max_items(1)
+        .with_max_depth(0)
+        .to_colored_str(r#"[{"a": [1]}, 2]"#, ColorMode::Off)?;
+    assert_eq!(s, "[…]");
+
+    let s = ColoredFormatter::new(PrettyFormatter::new())
+        .with_max_items(0)
+        .to_colored_str(r#"{"a": [1, 2], "b": {"c": 3}}"#, ColorMode::Off)?;
+    assert_eq!(s, "{\n  … 2 more items\n}");
+
+    // the markers get the elided style
+    let s = ColoredFormatter::with_styler(
+        CompactFormatter {},
+        Styler::default().with_elided(Style::new(Color::Red)),
+    )
+    .with_max_items(1)
+    .to_colored_str("[1, 2]", ColorMode::On)?;
+    assert_eq!(
+        s,
+        "\u{1b}[1m[\u{1b}[0m1,\u{1b}[31m… 1 more item\u{1b}[0m\u{1b}[1m]\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff() -> Result<(), Box<dyn Error>> {
+    use colored_json::diff::{Diff, Layout};
+
+    let old = json!({"a": 1, "b": [true, null], "c": {"d": "x"}});
+    let new = json!({"a": 1, "b": [false], "e": 2.5});
+
+    let s = diff::to_colored_diff(&old, &new, ColorMode::Off)?;
+    assert_eq!(
+        s,
+        r#"  {
+    "a": 1,
+    "b": [
+-     true,
++     false
+-     null
+    ],
+-   "c": {
+-     "d": "x"
+-   }
++   "e": 2.5
+  }"#
+    );
+
+    let s = Diff::new()
+        .with_layout(Layout::SideBySide)
+        .to_colored_diff(&old, &new, ColorMode::Off)?;
+    assert_eq!(
+        s,
+        r#"{              {
+  "a": 1,        "a": 1,
+  "b": [         "b": [
+    true,    |     false
+    null     < 
+  ],             ],
+  "c": {     < 
+    "d": "x" < 
+  }          < 
+             >   "e": 2.5
+}              }"#
+    );
+
+    // all markers have the same width, so the columns line up for removed rows as well
+    for line in s.lines() {
+        assert!(
+            ["   ", " | ", " < ", " > "].contains(&&line[12..15]),
+            "{line:?}"
+        );
+    }
+
+    let s = Diff::new()
+        .with_layout(Layout::ChangedOnly)
+        .to_colored_diff(&old, &new, ColorMode::Off)?;
+    assert_eq!(
+        s,
+        "~ /b/0: true → false\n- /b/1: null\n- /c: {\"d\":\"x\"}\n+ /e: 2.5"
+    );
+
+    // the root has no path, and equal documents have no changes
+    let s = Diff::new()
+        .with_layout(Layout::ChangedOnly)
+        .to_colored_diff(&1, &[1], ColorMode::Off)?;
+    assert_eq!(s, "~ 1 → [1]");
+    let s = Diff::new()
+        .with_layout(Layout::ChangedOnly)
+        .to_colored_diff(&old, &old, ColorMode::Off)?;
+    assert_eq!(s, "");
+
+    // unchanged lines get the styles of their tokens, changes the styles of the diff
+    let s = diff::to_colored_diff(&json!({"a": [1]}), &json!({"a": [2]}), ColorMode::On)?;
+    assert_eq!(
+        s,
+        "  \u{1b}[1m{\u{1b}[0m\n    \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34ma\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: \u{1b}[1m[\u{1b}[0m\n\
+         \u{1b}[31m-     1\u{1b}[0m\n\u{1b}[32m+     2\u{1b}[0m\n    \u{1b}[1m]\u{1b}[0m\n  \u{1b}[1m}\u{1b}[0m"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_color_mode_for_tests() {
+    struct TestRun {
+        vars: &'static [(&'static str, &'static str)],
+        args: &'static [&'static str],
+    }
+
+    impl Environment for TestRun {
+        fn var(&self, name: &str) -> Option<String> {
+            self.vars
+                .iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+
+        fn is_terminal(&self, _output: Output) -> bool {
+            true
+        }
+
+        fn args(&self) -> Vec<String> {
+            self.args.iter().map(|arg| arg.to_string()).collect()
+        }
+    }
+
+    let mode = |vars, args| ColorMode::for_tests_with(&TestRun { vars, args });
+
+    // captured by default
+    assert_eq!(mode(&[], &[]), ColorMode::Off);
+    assert_eq!(mode(&[], &["--nocapture"]), ColorMode::On);
+    assert_eq!(mode(&[("RUST_TEST_NOCAPTURE", "1")], &[]), ColorMode::On);
+    assert_eq!(mode(&[("RUST_TEST_NOCAPTURE", "0")], &[]), ColorMode::Off);
+
+    assert_eq!(mode(&[("CI", "true")], &["--nocapture"]), ColorMode::Off);
+    assert_eq!(mode(&[("CI", "false")], &["--nocapture"]), ColorMode::On);
+    assert_eq!(mode(&[("NO_COLOR", "1")], &["--nocapture"]), ColorMode::Off);
+    assert_eq!(
+        mode(&[("CLICOLOR_FORCE", "1"), ("CI", "1")], &[]),
+        ColorMode::On
+    );
+}
+
+#[test]
+fn test_assert_json_eq() {
+    #[derive(serde::Serialize)]
+    struct Person {
+        age: u32,
+        name: &'static str,
+    }
+
+    assert_json_eq!(
+        Person {
+            age: 43,
+            name: "John"
+        },
+        json!({"age": 43, "name": "John"})
+    );
+
+    let left = json!({"age": 43, "name": "John"});
+    let right = json!({"age": 44, "name": "John"});
+    let message = |result: std::thread::Result<()>| match result {
+        Err(payload) => payload.downcast::<String>().map(|s| *s).unwrap_or_default(),
+        Ok(()) => panic!("the assertion didn't fail"),
+    };
+    let diff = diff::to_colored_diff(&left, &right, ColorMode::for_tests()).unwrap();
+
+    let result = std::panic::catch_unwind(|| assert_json_eq!(left, right));
+    assert_eq!(
+        message(result),
+        format!("assertion `left == right` failed\n(- left, + right)\n{diff}")
+    );
+
+    let result = std::panic::catch_unwind(|| assert_json_eq!(left, right, "person {}", 1));
+    assert_eq!(
+        message(result),
+        format!("assertion `left == right` failed: person 1\n(- left, + right)\n{diff}")
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing() -> Result<(), Box<dyn Error>> {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let log = |ansi: bool| {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(ansi)
+            .without_time()
+            .with_level(false)
+            .with_target(false)
+            .fmt_fields(JsonFields::new().with_styler(Styler {
+                key: Style::new(Color::Green),
+                ..Default::default()
+            }))
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(body = %ColoredJson(&json!({"a": 1})), id = 7, "request");
+        });
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    };
+
+    // the subscriber decides about color, not the color mode
+    assert_eq!(log(false), "request body={\n  \"a\": 1\n} id=7\n");
+    assert_eq!(
+        log(true),
+        "request \u{1b}[3mbody\u{1b}[0m\u{1b}[2m=\u{1b}[0m\u{1b}[1m{\u{1b}[0m\n  \
+         \u{1b}[32m\"\u{1b}[0m\u{1b}[32ma\u{1b}[0m\u{1b}[32m\"\u{1b}[0m: 1\u{1b}[1m\n}\u{1b}[0m \
+         \u{1b}[3mid\u{1b}[0m\u{1b}[2m=\u{1b}[0m7\n"
+    );
+
+    // outside of the field formatter, the value is not colored
+    let value = json!([1]);
+    assert_eq!(format!("{}", ColoredJson(&value)), "[\n  1\n]");
+    assert_eq!(format!("{:?}", ColoredJson(&value)), "[1]");
+
+    Ok(())
+}